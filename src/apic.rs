@@ -0,0 +1,357 @@
+mod mmio;
+pub mod register;
+pub mod startup;
+mod x2apic;
+
+pub use mmio::{MmioAccess, VolatileMmio, XApicBackend};
+pub use x2apic::X2ApicBackend;
+
+use ::register::RegisterBufferWriter;
+
+use crate::{cpuid::feature::StdFeature, ArchError};
+
+use self::register::{
+    lvt_fields, ApicVersion, ErrorStatus, Icr, LvtEntry, SpuriousVector, TimerDivide, TimerMode,
+};
+
+/// xAPIC 模式下寄存器相对于 APIC 基址的偏移，x2APIC 模式下的 MSR 地址由偏移换算而来。
+pub mod offset {
+    pub const ID: u32 = 0x020;
+    pub const VERSION: u32 = 0x030;
+    pub const TPR: u32 = 0x080;
+    pub const PPR: u32 = 0x0a0;
+    pub const EOI: u32 = 0x0b0;
+    pub const LDR: u32 = 0x0d0;
+    /// 仅 xAPIC
+    pub const DFR: u32 = 0x0e0;
+    pub const SVR: u32 = 0x0f0;
+    pub const ISR: u32 = 0x100;
+    pub const TMR: u32 = 0x180;
+    pub const IRR: u32 = 0x200;
+    pub const ESR: u32 = 0x280;
+    pub const LVT_CMCI: u32 = 0x2f0;
+    pub const ICR_LOW: u32 = 0x300;
+    /// 仅 xAPIC
+    pub const ICR_HIGH: u32 = 0x310;
+    pub const LVT_TIMER: u32 = 0x320;
+    pub const LVT_THERMAL: u32 = 0x330;
+    pub const LVT_PERFMON: u32 = 0x340;
+    pub const LVT_LINT0: u32 = 0x350;
+    pub const LVT_LINT1: u32 = 0x360;
+    pub const LVT_ERROR: u32 = 0x370;
+    pub const TIMER_INITIAL_COUNT: u32 = 0x380;
+    pub const TIMER_CURRENT_COUNT: u32 = 0x390;
+    pub const TIMER_DIVIDE: u32 = 0x3e0;
+    /// 仅 x2APIC
+    pub const SELF_IPI: u32 = 0x3f0;
+}
+
+/// # 本地 APIC 后端
+///
+/// 屏蔽 xAPIC（MMIO）与 x2APIC（MSR）两种访问方式的差异，寄存器统一使用 xAPIC 偏移（参见 [`offset`]）。
+pub trait ApicBackend {
+    const X2APIC: bool;
+
+    fn read(&self, offset: u32) -> u32;
+    fn write(&mut self, offset: u32, value: u32);
+    /// 高 32 bit 为目标字段
+    fn read_icr(&self) -> u64;
+    fn write_icr(&mut self, value: u64);
+}
+
+/// LVT 表项
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LvtRegister {
+    Timer,
+    Thermal,
+    PerfMon,
+    Lint0,
+    Lint1,
+    Error,
+    Cmci,
+}
+
+impl LvtRegister {
+    pub fn offset(self) -> u32 {
+        match self {
+            LvtRegister::Timer => offset::LVT_TIMER,
+            LvtRegister::Thermal => offset::LVT_THERMAL,
+            LvtRegister::PerfMon => offset::LVT_PERFMON,
+            LvtRegister::Lint0 => offset::LVT_LINT0,
+            LvtRegister::Lint1 => offset::LVT_LINT1,
+            LvtRegister::Error => offset::LVT_ERROR,
+            LvtRegister::Cmci => offset::LVT_CMCI,
+        }
+    }
+    /// 该表项存在时，版本寄存器中 LVT 表项数量的最小值
+    fn min_entries(self) -> u8 {
+        match self {
+            LvtRegister::PerfMon => 5,
+            LvtRegister::Thermal => 6,
+            LvtRegister::Cmci => 7,
+            _ => 4,
+        }
+    }
+}
+
+/// # 本地 APIC
+///
+/// ```not test
+/// let apic_base = msr.buffer::<ApicBase>();
+/// let mmio = unsafe { VolatileMmio::from_apic_base(&apic_base, phys_to_virt)? };
+/// let mut apic = LocalApic::new(XApicBackend::new(mmio));
+/// apic.set_spurious(SpuriousVector::enabled(0xff));
+/// ```
+pub struct LocalApic<B: ApicBackend> {
+    backend: B,
+}
+
+impl<B: ApicBackend> LocalApic<B> {
+    pub fn new(backend: B) -> Self {
+        Self { backend }
+    }
+    pub fn backend(&mut self) -> &mut B {
+        &mut self.backend
+    }
+    pub fn into_inner(self) -> B {
+        self.backend
+    }
+
+    /// 本地 APIC ID，xAPIC 模式下为 8 bit，x2APIC 模式下为 32 bit。
+    pub fn id(&self) -> u32 {
+        let id = self.backend.read(offset::ID);
+        if B::X2APIC {
+            id
+        } else {
+            id >> 24
+        }
+    }
+    pub fn version(&self) -> ApicVersion {
+        ApicVersion {
+            data: self.backend.read(offset::VERSION),
+        }
+    }
+
+    /// 任务优先级类别（TPR[7:4]），与 [`cr8::fields::TRR`](crate::cr::cr8::fields::TRR) 的取值相同。
+    ///
+    /// 64 bit 模式下写入 CR8 或 TPR 中的任意一个，都会同步到另一个。
+    pub fn task_priority(&self) -> u8 {
+        (self.backend.read(offset::TPR) >> 4) as u8 & 0xf
+    }
+    /// 优先级类别不高于 `class` 的中断会被屏蔽，TPR[3:0] 清 0。
+    pub fn set_task_priority(&mut self, class: u8) {
+        self.backend.write(offset::TPR, ((class & 0xf) as u32) << 4)
+    }
+    /// 处理器优先级类别（PPR[7:4]），由 TPR 和 ISR 中优先级最高的中断共同决定。
+    pub fn processor_priority(&self) -> u8 {
+        (self.backend.read(offset::PPR) >> 4) as u8 & 0xf
+    }
+
+    /// 通知本地 APIC 当前中断处理结束，x2APIC 模式下写入非 0 值会导致 #GP 异常。
+    pub fn eoi(&mut self) {
+        self.backend.write(offset::EOI, 0)
+    }
+
+    pub fn spurious(&self) -> SpuriousVector {
+        SpuriousVector {
+            data: self.backend.read(offset::SVR),
+        }
+    }
+    pub fn set_spurious(&mut self, svr: SpuriousVector) {
+        self.backend.write(offset::SVR, svr.data)
+    }
+
+    /// 处理器不支持该表项时返回 None
+    pub fn lvt(&self, register: LvtRegister) -> Option<LvtEntry> {
+        if self.version().lvt_entries() < register.min_entries() {
+            return None;
+        }
+        Some(LvtEntry {
+            data: self.backend.read(register.offset()),
+        })
+    }
+    /// 处理器不支持该表项时返回错误 `ArchError::IndexOutOfRange`。
+    ///
+    /// 定时器表项需要检查定时器模式，只能通过 [`set_timer`](Self::set_timer) 写入，
+    /// `register` 为 [`LvtRegister::Timer`] 时同样返回错误 `ArchError::IndexOutOfRange`。
+    pub fn set_lvt(&mut self, register: LvtRegister, entry: LvtEntry) -> Result<(), ArchError> {
+        if register == LvtRegister::Timer || self.version().lvt_entries() < register.min_entries() {
+            return Err(ArchError::IndexOutOfRange);
+        }
+        self.backend.write(register.offset(), entry.data);
+        Ok(())
+    }
+
+    /// 写入定时器表项，TSC-deadline 模式需要 `CPUID.01H:ECX.TSC_Deadline[24] = 1`，否则返回错误 `ArchError::FeatureIsNotSupported`。
+    ///
+    /// 单次和周期模式下，写入初始计数后定时器开始计数；TSC-deadline 模式下需要写入 IA32_TSC_DEADLINE。
+    pub fn set_timer(
+        &mut self,
+        mode: TimerMode,
+        mut entry: LvtEntry,
+        std_feature: &StdFeature,
+    ) -> Result<(), ArchError> {
+        if mode == TimerMode::TSC_DEADLINE && !std_feature.support_tsc_deadline() {
+            return Err(ArchError::FeatureIsNotSupported);
+        }
+        entry.write::<lvt_fields::TIMER_MODE>(mode);
+        self.backend.write(offset::LVT_TIMER, entry.data);
+        Ok(())
+    }
+    pub fn set_timer_divide(&mut self, divide: TimerDivide) {
+        self.backend.write(offset::TIMER_DIVIDE, divide.data as u32)
+    }
+    /// 写入 0 会停止定时器
+    pub fn set_timer_initial_count(&mut self, count: u32) {
+        self.backend.write(offset::TIMER_INITIAL_COUNT, count)
+    }
+    pub fn timer_current_count(&self) -> u32 {
+        self.backend.read(offset::TIMER_CURRENT_COUNT)
+    }
+
+    /// 读取并清除错误状态，读取前需要先写入 ESR 以更新其内容。
+    pub fn error_status(&mut self) -> ErrorStatus {
+        self.backend.write(offset::ESR, 0);
+        ErrorStatus {
+            data: self.backend.read(offset::ESR),
+        }
+    }
+
+    /// 发送 IPI，xAPIC 模式下目标 ID 超过 8 bit 时返回错误 `ArchError::InvalidDestination`。
+    pub fn send_ipi(&mut self, icr: Icr) -> Result<(), ArchError> {
+        let value = if B::X2APIC {
+            icr.data
+        } else {
+            let destination = icr.destination();
+            if destination > 0xff {
+                return Err(ArchError::InvalidDestination);
+            }
+            (icr.data & 0xffff_ffff) | ((destination as u64) << 56)
+        };
+        self.backend.write_icr(value);
+        Ok(())
+    }
+    /// 上一个 IPI 是否尚未发送完成，x2APIC 模式下没有投递状态，始终为 false。
+    pub fn ipi_pending(&self) -> bool {
+        if B::X2APIC {
+            return false;
+        }
+        let icr = Icr {
+            data: self.backend.read_icr(),
+        };
+        icr.delivery_pending()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ::register::RegisterBufferWriter;
+
+    use crate::{
+        cpuid::feature::StdFeature,
+        msr::{apic::ApicBaseBuffer, FakeMsr, MsrAccess, MsrBuffer},
+        ArchError, Clean,
+    };
+
+    use super::{
+        register::{lvt_fields, DeliveryMode, Icr, LvtEntry, SpuriousVector, TimerMode},
+        LocalApic, LvtRegister, MmioAccess, X2ApicBackend, XApicBackend,
+    };
+
+    struct FakeMmio {
+        page: [u8; 0x400],
+    }
+
+    impl MmioAccess for FakeMmio {
+        fn read_u32(&self, offset: u32) -> u32 {
+            let offset = offset as usize;
+            let mut bytes = [0; 4];
+            bytes.copy_from_slice(&self.page[offset..offset + 4]);
+            u32::from_le_bytes(bytes)
+        }
+        fn write_u32(&mut self, offset: u32, value: u32) {
+            let offset = offset as usize;
+            self.page[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+        }
+    }
+
+    #[test]
+    fn xapic() {
+        let mut mmio = FakeMmio { page: [0; 0x400] };
+        mmio.write_u32(0x20, 0x0300_0000);
+        mmio.write_u32(0x30, 0x0005_0014);
+        mmio.write_u32(0xb0, 0xffff_ffff);
+        let mut apic = LocalApic::new(XApicBackend::new(mmio));
+
+        assert_eq!(apic.id(), 3);
+        assert_eq!(apic.version().version(), 0x14);
+        assert_eq!(apic.version().lvt_entries(), 6);
+        assert!(apic.lvt(LvtRegister::Thermal).is_some());
+        assert!(apic.lvt(LvtRegister::Cmci).is_none());
+
+        apic.set_task_priority(3);
+        assert_eq!(apic.task_priority(), 3);
+        apic.eoi();
+        apic.set_spurious(SpuriousVector::enabled(0xff));
+        assert!(apic.spurious().is_enabled());
+
+        apic.send_ipi(Icr::init(1)).unwrap();
+        assert!(matches!(
+            apic.send_ipi(Icr::fixed(0x40, 0x100)),
+            Err(ArchError::InvalidDestination)
+        ));
+        assert!(!apic.ipi_pending());
+
+        let mmio = apic.into_inner().into_inner();
+        assert_eq!(mmio.read_u32(0x80), 0x30);
+        assert_eq!(mmio.read_u32(0xb0), 0);
+        assert_eq!(mmio.read_u32(0xf0), 0x1ff);
+        assert_eq!(mmio.read_u32(0x300), 0x4500);
+        assert_eq!(mmio.read_u32(0x310), 0x0100_0000);
+    }
+
+    #[test]
+    fn x2apic() {
+        let apic_base = Clean {
+            raw_buffer: ApicBaseBuffer::from_raw(0xfee0_0d00),
+        };
+        let msr = FakeMsr::new(&[(0x802, 0x100), (0x803, 0x0106_0015)]);
+        let backend = X2ApicBackend::new(msr, &apic_base).unwrap();
+        let mut apic = LocalApic::new(backend);
+        assert_eq!(apic.id(), 0x100);
+        assert!(apic.version().support_eoi_suppression());
+
+        apic.send_ipi(Icr::startup(0x08, 0x100)).unwrap();
+        apic.eoi();
+        apic.set_lvt(LvtRegister::Lint0, LvtEntry::masked())
+            .unwrap();
+        let icr = Icr::new(0, DeliveryMode::NMI, 2);
+        assert_eq!(icr.delivery_mode(), DeliveryMode::NMI);
+
+        let std_feature = StdFeature::default();
+        // 不能绕过 set_timer 对 TSC-deadline 模式的检查
+        let mut entry = LvtEntry::fixed(0x20);
+        entry.write::<lvt_fields::TIMER_MODE>(TimerMode::TSC_DEADLINE);
+        assert!(matches!(
+            apic.set_lvt(LvtRegister::Timer, entry),
+            Err(ArchError::IndexOutOfRange)
+        ));
+        assert!(matches!(
+            apic.set_timer(TimerMode::TSC_DEADLINE, LvtEntry::fixed(0x20), &std_feature),
+            Err(ArchError::FeatureIsNotSupported)
+        ));
+        apic.set_timer(TimerMode::PERIODIC, LvtEntry::fixed(0x20), &std_feature)
+            .unwrap();
+
+        let msr = apic.into_inner().into_inner();
+        assert_eq!(msr.read_msr(0x830), 0x0000_0100_0000_4608);
+        assert_eq!(msr.read_msr(0x80b), 0);
+        assert_eq!(msr.read_msr(0x835), 0x0001_0000);
+        assert_eq!(msr.read_msr(0x832), 0x0002_0020);
+
+        let xapic_base = Clean {
+            raw_buffer: ApicBaseBuffer::from_raw(0xfee0_0900),
+        };
+        assert!(X2ApicBackend::new(msr, &xapic_base).is_none());
+    }
+}
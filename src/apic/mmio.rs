@@ -0,0 +1,89 @@
+use crate::{
+    msr::apic::{ApicBaseBuffer, ApicMode},
+    Clean,
+};
+
+use super::{offset, ApicBackend};
+
+/// # MMIO 访问接口
+///
+/// `offset` 为寄存器相对于 APIC 基址的偏移，测试时可以替换为一段字节数组。
+pub trait MmioAccess {
+    fn read_u32(&self, offset: u32) -> u32;
+    fn write_u32(&mut self, offset: u32, value: u32);
+}
+
+/// 通过 volatile 读写访问映射后的 APIC 寄存器
+pub struct VolatileMmio {
+    base: usize,
+}
+
+impl VolatileMmio {
+    /// `base` 为 APIC 寄存器页映射后的虚拟地址，该页需要映射为 UC 类型。
+    pub unsafe fn new(base: usize) -> Self {
+        Self { base }
+    }
+
+    /// 从 IA32_APIC_BASE 中读取物理基址，并通过 `map` 转换为虚拟地址。
+    ///
+    /// 本地 APIC 不处于 xAPIC 模式时返回 None。
+    pub unsafe fn from_apic_base<F: FnOnce(u64) -> usize>(
+        apic_base: &Clean<ApicBaseBuffer>,
+        map: F,
+    ) -> Option<Self> {
+        if apic_base.mode()? != ApicMode::XApic {
+            return None;
+        }
+        Some(Self::new(map(apic_base.base())))
+    }
+}
+
+impl MmioAccess for VolatileMmio {
+    #[inline]
+    fn read_u32(&self, offset: u32) -> u32 {
+        unsafe { core::ptr::read_volatile((self.base + offset as usize) as *const u32) }
+    }
+    #[inline]
+    fn write_u32(&mut self, offset: u32, value: u32) {
+        unsafe { core::ptr::write_volatile((self.base + offset as usize) as *mut u32, value) }
+    }
+}
+
+/// # xAPIC 后端
+///
+/// 寄存器位于 4KB 的 MMIO 页中，每个寄存器按 16 字节对齐，只能以 32 bit 宽度访问。
+pub struct XApicBackend<M: MmioAccess> {
+    mmio: M,
+}
+
+impl<M: MmioAccess> XApicBackend<M> {
+    pub fn new(mmio: M) -> Self {
+        Self { mmio }
+    }
+    pub fn into_inner(self) -> M {
+        self.mmio
+    }
+}
+
+impl<M: MmioAccess> ApicBackend for XApicBackend<M> {
+    const X2APIC: bool = false;
+
+    #[inline]
+    fn read(&self, offset: u32) -> u32 {
+        self.mmio.read_u32(offset)
+    }
+    #[inline]
+    fn write(&mut self, offset: u32, value: u32) {
+        self.mmio.write_u32(offset, value)
+    }
+    fn read_icr(&self) -> u64 {
+        let low = self.mmio.read_u32(offset::ICR_LOW);
+        let high = self.mmio.read_u32(offset::ICR_HIGH);
+        (low as u64) | ((high as u64) << 32)
+    }
+    /// 写入低 32 bit 时才会发送 IPI，因此需要先写高 32 bit。
+    fn write_icr(&mut self, value: u64) {
+        self.mmio.write_u32(offset::ICR_HIGH, (value >> 32) as u32);
+        self.mmio.write_u32(offset::ICR_LOW, value as u32);
+    }
+}
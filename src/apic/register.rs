@@ -0,0 +1,373 @@
+use register::RegisterBufferWriter;
+
+/// # 投递模式
+///
+/// LVT 表项和 ICR 中的 `[10:8]`。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeliveryMode {
+    pub(crate) data: u8,
+}
+
+def_const! {
+    DeliveryMode {
+        /// 投递 vector 字段所指定的中断
+        FIXED:0b000,
+        /// 投递给目标中优先级最低的处理器，仅用于 ICR
+        LOWEST_PRIORITY:0b001,
+        /// 系统管理中断，vector 字段需要为 0
+        SMI:0b010,
+        /// 不可屏蔽中断，忽略 vector 字段
+        NMI:0b100,
+        /// 使目标处理器进入 INIT 状态，忽略 vector 字段
+        INIT:0b101,
+        /// Start-up IPI，vector 字段为启动代码所在的 4KB 页号，仅用于 ICR
+        START_UP:0b110,
+        /// 由外部的 8259A 兼容中断控制器提供 vector，仅用于 LVT
+        EXT_INT:0b111,
+    }
+}
+
+/// # 定时器模式
+///
+/// LVT 定时器表项中的 `[18:17]`。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimerMode {
+    pub(crate) data: u8,
+}
+
+def_const! {
+    TimerMode {
+        /// 计数到 0 后触发一次中断
+        ONE_SHOT:0b00,
+        /// 计数到 0 后触发中断，并重新从初始计数开始
+        PERIODIC:0b01,
+        /// 时间戳计数器达到 IA32_TSC_DEADLINE 时触发中断，
+        /// 需要 `CPUID.01H:ECX.TSC_Deadline[24] = 1`
+        TSC_DEADLINE:0b10,
+    }
+}
+
+/// # 目标简写
+///
+/// ICR 中的 `[19:18]`，不为 NONE 时忽略目标字段。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Shorthand {
+    pub(crate) data: u8,
+}
+
+def_const! {
+    Shorthand {
+        NONE:0b00,
+        SELF:0b01,
+        ALL_INCLUDING_SELF:0b10,
+        ALL_EXCLUDING_SELF:0b11,
+    }
+}
+
+/// # 定时器分频
+///
+/// 分频配置寄存器中的 bit 0、1、3。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimerDivide {
+    pub(crate) data: u8,
+}
+
+def_const! {
+    TimerDivide {
+        BY_1:0b1011,
+        BY_2:0b0000,
+        BY_4:0b0001,
+        BY_8:0b0010,
+        BY_16:0b0011,
+        BY_32:0b1000,
+        BY_64:0b1001,
+        BY_128:0b1010,
+    }
+}
+
+/// # 版本寄存器
+#[derive(Debug, Clone, Copy)]
+pub struct ApicVersion {
+    pub(crate) data: u32,
+}
+
+impl ApicVersion {
+    /// LVT 表项的数量
+    pub fn lvt_entries(&self) -> u8 {
+        self.max_lvt() + 1
+    }
+}
+
+impl_reg_buffer_trait!(ApicVersion);
+
+plain_field! {
+    ApicVersion {
+        /// 0x1X 为集成的本地 APIC
+        pub version:                version_fields::VERSION,
+        max_lvt:                    version_fields::MAX_LVT,
+        /// 支持通过 [`SpuriousVector`] 抑制 EOI 广播
+        pub support_eoi_suppression: version_fields::EOI_SUPPRESSION,
+    }
+}
+
+pub mod version_fields {
+    bits::fields_ex! {
+        super::ApicVersion [data] {
+            pub VERSION         [00..=07, ro, u8],
+            /// LVT 表项的数量减 1
+            pub MAX_LVT         [16..=23, ro, u8],
+            pub EOI_SUPPRESSION [24, ro, bool],
+        }
+    }
+}
+
+/// # 伪中断向量寄存器
+/// Spurious-Interrupt Vector Register
+///
+/// ENABLE 为 0 时本地 APIC 被软件禁用，此时所有 LVT 表项均被屏蔽。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpuriousVector {
+    pub(crate) data: u32,
+}
+
+impl SpuriousVector {
+    /// 软件使能本地 APIC，并将伪中断向量设置为 `vector`。
+    pub fn enabled(vector: u8) -> Self {
+        let mut svr = Self { data: 0 };
+        svr.write::<svr_fields::VECTOR>(vector)
+            .write::<svr_fields::ENABLE>(true);
+        svr
+    }
+}
+
+impl_reg_buffer_trait!(SpuriousVector);
+
+plain_field! {
+    SpuriousVector {
+        pub vector:                 svr_fields::VECTOR,
+        pub is_enabled:             svr_fields::ENABLE,
+        pub focus_check_disabled:   svr_fields::FOCUS_DISABLE,
+        pub eoi_suppressed:         svr_fields::EOI_SUPPRESSION,
+    }
+}
+
+pub mod svr_fields {
+    bits::fields_ex! {
+        super::SpuriousVector [data] {
+            pub VECTOR          [00..=07, rw, u8],
+            /// APIC 软件使能
+            pub ENABLE          [08, rw, bool],
+            /// 禁用最低优先级投递中的焦点处理器检查
+            pub FOCUS_DISABLE   [09, rw, bool],
+            /// 电平触发中断的 EOI 不再广播给 I/O APIC
+            pub EOI_SUPPRESSION [12, rw, bool],
+        }
+    }
+}
+
+/// # LVT 表项
+/// Local Vector Table
+///
+/// 不同的表项支持的字段不同：TIMER_MODE 仅用于定时器，POLARITY、REMOTE_IRR、LEVEL_TRIGGERED 仅用于 LINT0/LINT1，
+/// 定时器和错误表项只支持 FIXED 投递模式。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LvtEntry {
+    pub(crate) data: u32,
+}
+
+impl LvtEntry {
+    /// 复位后的值，仅 MASKED 置 1
+    pub const fn masked() -> Self {
+        Self { data: 1 << 16 }
+    }
+    /// 未屏蔽、以 FIXED 模式投递 `vector` 的表项
+    pub fn fixed(vector: u8) -> Self {
+        let mut entry = Self { data: 0 };
+        entry.write::<lvt_fields::VECTOR>(vector);
+        entry
+    }
+}
+
+impl_reg_buffer_trait!(LvtEntry);
+
+plain_field! {
+    LvtEntry {
+        pub vector:             lvt_fields::VECTOR,
+        pub delivery_mode:      lvt_fields::DELIVERY_MODE,
+        /// 中断已发出，但尚未被处理器接收
+        pub delivery_pending:   lvt_fields::DELIVERY_STATUS,
+        pub active_low:         lvt_fields::POLARITY,
+        pub remote_irr:         lvt_fields::REMOTE_IRR,
+        pub level_triggered:    lvt_fields::LEVEL_TRIGGERED,
+        pub is_masked:          lvt_fields::MASKED,
+        pub timer_mode:         lvt_fields::TIMER_MODE,
+    }
+}
+
+pub mod lvt_fields {
+    use super::{DeliveryMode, TimerMode};
+
+    bits::fields_ex! {
+        super::LvtEntry [data] {
+            pub VECTOR          [00..=07, rw, u8],
+            pub DELIVERY_MODE   [08..=10, rw, DeliveryMode] {
+                input_converter: |x: DeliveryMode| x.data as u32;
+                output_converter: |data| DeliveryMode { data: data as u8 }
+            },
+            pub DELIVERY_STATUS [12, ro, bool],
+            /// 输入引脚极性，置 1 时低电平有效
+            pub POLARITY        [13, rw, bool],
+            pub REMOTE_IRR      [14, ro, bool],
+            /// 置 1 时为电平触发，否则为边沿触发
+            pub LEVEL_TRIGGERED [15, rw, bool],
+            pub MASKED          [16, rw, bool],
+            pub TIMER_MODE      [17..=18, rw, TimerMode] {
+                input_converter: |x: TimerMode| x.data as u32;
+                output_converter: |data| TimerMode { data: data as u8 }
+            },
+        }
+    }
+}
+
+/// # 错误状态寄存器
+/// Error Status Register
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorStatus {
+    pub(crate) data: u32,
+}
+
+impl ErrorStatus {
+    pub fn has_error(&self) -> bool {
+        self.data & 0xff != 0
+    }
+}
+
+impl_reg_buffer_trait!(ErrorStatus);
+
+plain_field! {
+    ErrorStatus {
+        pub send_checksum:          esr_fields::SEND_CHECKSUM,
+        pub receive_checksum:       esr_fields::RECEIVE_CHECKSUM,
+        pub send_accept:            esr_fields::SEND_ACCEPT,
+        pub receive_accept:         esr_fields::RECEIVE_ACCEPT,
+        pub redirectable_ipi:       esr_fields::REDIRECTABLE_IPI,
+        pub send_illegal_vector:    esr_fields::SEND_ILLEGAL_VECTOR,
+        pub receive_illegal_vector: esr_fields::RECEIVE_ILLEGAL_VECTOR,
+        pub illegal_register:       esr_fields::ILLEGAL_REGISTER,
+    }
+}
+
+pub mod esr_fields {
+    bits::fields_ex! {
+        super::ErrorStatus [data] {
+            pub SEND_CHECKSUM           [00, ro, bool],
+            pub RECEIVE_CHECKSUM        [01, ro, bool],
+            pub SEND_ACCEPT             [02, ro, bool],
+            pub RECEIVE_ACCEPT          [03, ro, bool],
+            /// 不支持以最低优先级模式发送 IPI
+            pub REDIRECTABLE_IPI        [04, ro, bool],
+            pub SEND_ILLEGAL_VECTOR     [05, ro, bool],
+            pub RECEIVE_ILLEGAL_VECTOR  [06, ro, bool],
+            /// 访问了不存在的寄存器
+            pub ILLEGAL_REGISTER        [07, ro, bool],
+        }
+    }
+}
+
+/// # 中断命令寄存器
+/// Interrupt Command Register
+///
+/// 用于向其他处理器发送 IPI。DESTINATION 保存目标的 APIC ID：
+/// x2APIC 模式下为完整的 32 bit，xAPIC 模式下只有低 8 bit 有效，
+/// 由 [`LocalApic::send_ipi`](super::LocalApic::send_ipi) 转换为对应的格式。
+///
+/// ```not test
+/// let icr = Icr::fixed(0x40, 1).with_shorthand(Shorthand::ALL_EXCLUDING_SELF);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Icr {
+    pub(crate) data: u64,
+}
+
+impl Icr {
+    /// 物理目标模式、边沿触发、assert 的 IPI
+    pub fn new(vector: u8, delivery_mode: DeliveryMode, destination: u32) -> Self {
+        let mut icr = Self { data: 0 };
+        icr.write::<icr_fields::VECTOR>(vector)
+            .write::<icr_fields::DELIVERY_MODE>(delivery_mode)
+            .write::<icr_fields::ASSERT>(true)
+            .write::<icr_fields::DESTINATION>(destination);
+        icr
+    }
+    pub fn fixed(vector: u8, destination: u32) -> Self {
+        Self::new(vector, DeliveryMode::FIXED, destination)
+    }
+    pub fn nmi(destination: u32) -> Self {
+        Self::new(0, DeliveryMode::NMI, destination)
+    }
+    pub fn init(destination: u32) -> Self {
+        Self::new(0, DeliveryMode::INIT, destination)
+    }
+    /// 电平触发的 INIT de-assert，仅 Pentium 和 P6 系列处理器需要，之后的处理器会忽略。
+    pub fn init_deassert(destination: u32) -> Self {
+        let mut icr = Self::init(destination);
+        icr.write::<icr_fields::ASSERT>(false)
+            .write::<icr_fields::LEVEL_TRIGGERED>(true);
+        icr
+    }
+    /// 目标处理器从物理地址 `page * 4KB` 处开始以实模式执行
+    pub fn startup(page: u8, destination: u32) -> Self {
+        Self::new(page, DeliveryMode::START_UP, destination)
+    }
+
+    pub fn with_shorthand(mut self, shorthand: Shorthand) -> Self {
+        self.write::<icr_fields::SHORTHAND>(shorthand);
+        self
+    }
+    /// 置 1 时 DESTINATION 为逻辑目标
+    pub fn with_logical(mut self, logical: bool) -> Self {
+        self.write::<icr_fields::LOGICAL>(logical);
+        self
+    }
+}
+
+impl_reg_buffer_trait!(Icr);
+
+plain_field! {
+    Icr {
+        pub vector:             icr_fields::VECTOR,
+        pub delivery_mode:      icr_fields::DELIVERY_MODE,
+        pub is_logical:         icr_fields::LOGICAL,
+        pub delivery_pending:   icr_fields::DELIVERY_STATUS,
+        pub is_assert:          icr_fields::ASSERT,
+        pub level_triggered:    icr_fields::LEVEL_TRIGGERED,
+        pub shorthand:          icr_fields::SHORTHAND,
+        pub destination:        icr_fields::DESTINATION,
+    }
+}
+
+pub mod icr_fields {
+    use super::{DeliveryMode, Shorthand};
+
+    bits::fields_ex! {
+        super::Icr [data] {
+            pub VECTOR          [00..=07, rw, u8],
+            pub DELIVERY_MODE   [08..=10, rw, DeliveryMode] {
+                input_converter: |x: DeliveryMode| x.data as u64;
+                output_converter: |data| DeliveryMode { data: data as u8 }
+            },
+            /// 目标模式，置 1 时为逻辑目标
+            pub LOGICAL         [11, rw, bool],
+            /// 仅 xAPIC 模式，IPI 尚未发送完成
+            pub DELIVERY_STATUS [12, ro, bool],
+            /// 除 INIT de-assert 外均需要置 1
+            pub ASSERT          [14, rw, bool],
+            pub LEVEL_TRIGGERED [15, rw, bool],
+            pub SHORTHAND       [18..=19, rw, Shorthand] {
+                input_converter: |x: Shorthand| x.data as u64;
+                output_converter: |data| Shorthand { data: data as u8 }
+            },
+            pub DESTINATION     [32..=63, rw, u32],
+        }
+    }
+}
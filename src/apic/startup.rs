@@ -0,0 +1,314 @@
+use core::fmt::Display;
+
+use super::{
+    register::{ErrorStatus, Icr},
+    ApicBackend, LocalApic,
+};
+
+/// # 延时源
+///
+/// 启动流程中的等待时间由调用者提供，例如基于 TSC、HPET 或 PIT 的忙等待。
+pub trait Delay {
+    fn delay_us(&mut self, us: u64);
+}
+
+/// 启动流程中的 IPI
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StartupStep {
+    Init,
+    InitDeassert,
+    FirstSipi,
+    SecondSipi,
+}
+
+/// 启动应用处理器时产生的错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StartupError {
+    /// xAPIC 模式下目标 APIC ID 超过 8 bit
+    InvalidDestination(u32),
+    /// 启动代码所在的页号为保留值（A0h-BFh）
+    InvalidTrampoline(u8),
+    /// 超时后 ICR 的投递状态仍为 pending
+    DeliveryTimeout(StartupStep),
+    /// 发送 IPI 后 ESR 报告了错误
+    DeliveryError(StartupStep, ErrorStatus),
+}
+
+impl Display for StartupError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            StartupError::InvalidDestination(id) => {
+                write!(f, "APIC ID {:#x} is not addressable in xAPIC mode", id)
+            }
+            StartupError::InvalidTrampoline(page) => {
+                write!(f, "start-up vector {:#x} is reserved", page)
+            }
+            StartupError::DeliveryTimeout(step) => write!(f, "{:?} IPI was not delivered", step),
+            StartupError::DeliveryError(step, esr) => {
+                write!(f, "{:?} IPI failed with ESR {:#x}", step, esr.data)
+            }
+        }
+    }
+}
+
+/// # 应用处理器启动流程
+///
+/// 按照 Intel SDM 8.4.4.1 中的 INIT-SIPI-SIPI 流程启动一个应用处理器：
+///
+/// 1. 清除 ESR；
+/// 2. 发送 INIT IPI（可选地再发送 INIT de-assert），等待 10ms；
+/// 3. 发送 Start-up IPI，等待 200us，检查 ESR；
+/// 4. 再次发送 Start-up IPI，等待 200us，检查 ESR。
+///
+/// 每次发送 IPI 后都会轮询 ICR 的投递状态，x2APIC 模式下没有投递状态，不需要轮询。
+///
+/// ```not test
+/// ApStartup::new().start(&mut apic, &mut delay, apic_id, 0x08)?;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ApStartup {
+    init_delay_us: u64,
+    sipi_delay_us: u64,
+    poll_interval_us: u64,
+    poll_limit: u32,
+    init_deassert: bool,
+}
+
+impl ApStartup {
+    pub const fn new() -> Self {
+        Self {
+            init_delay_us: 10_000,
+            sipi_delay_us: 200,
+            poll_interval_us: 10,
+            poll_limit: 100,
+            init_deassert: false,
+        }
+    }
+    /// INIT IPI 之后的等待时间，默认为 10ms
+    pub fn with_init_delay(mut self, us: u64) -> Self {
+        self.init_delay_us = us;
+        self
+    }
+    /// 每个 Start-up IPI 之后的等待时间，默认为 200us
+    pub fn with_sipi_delay(mut self, us: u64) -> Self {
+        self.sipi_delay_us = us;
+        self
+    }
+    /// 轮询投递状态的间隔和次数，默认为每 10us 一次，共 100 次
+    pub fn with_poll(mut self, interval_us: u64, limit: u32) -> Self {
+        self.poll_interval_us = interval_us;
+        self.poll_limit = limit;
+        self
+    }
+    /// 在 INIT 之后发送电平触发的 INIT de-assert，仅 Pentium 和 P6 系列处理器需要。
+    pub fn with_init_deassert(mut self, init_deassert: bool) -> Self {
+        self.init_deassert = init_deassert;
+        self
+    }
+
+    /// 启动 APIC ID 为 `apic_id` 的应用处理器，从物理地址 `page * 4KB` 处以实模式开始执行。
+    pub fn start<B: ApicBackend, D: Delay>(
+        &self,
+        apic: &mut LocalApic<B>,
+        delay: &mut D,
+        apic_id: u32,
+        page: u8,
+    ) -> Result<(), StartupError> {
+        if (0xa0..=0xbf).contains(&page) {
+            return Err(StartupError::InvalidTrampoline(page));
+        }
+        apic.error_status();
+
+        self.send(apic, delay, Icr::init(apic_id), StartupStep::Init)?;
+        if self.init_deassert {
+            self.send(
+                apic,
+                delay,
+                Icr::init_deassert(apic_id),
+                StartupStep::InitDeassert,
+            )?;
+        }
+        delay.delay_us(self.init_delay_us);
+
+        for &step in &[StartupStep::FirstSipi, StartupStep::SecondSipi] {
+            self.send(apic, delay, Icr::startup(page, apic_id), step)?;
+            delay.delay_us(self.sipi_delay_us);
+            let esr = apic.error_status();
+            if esr.has_error() {
+                return Err(StartupError::DeliveryError(step, esr));
+            }
+        }
+        Ok(())
+    }
+
+    fn send<B: ApicBackend, D: Delay>(
+        &self,
+        apic: &mut LocalApic<B>,
+        delay: &mut D,
+        icr: Icr,
+        step: StartupStep,
+    ) -> Result<(), StartupError> {
+        apic.send_ipi(icr)
+            .map_err(|_| StartupError::InvalidDestination(icr.destination()))?;
+        for _ in 0..self.poll_limit {
+            if !apic.ipi_pending() {
+                return Ok(());
+            }
+            delay.delay_us(self.poll_interval_us);
+        }
+        if apic.ipi_pending() {
+            return Err(StartupError::DeliveryTimeout(step));
+        }
+        Ok(())
+    }
+}
+
+impl Default for ApStartup {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::RefCell, vec, vec::Vec};
+
+    use crate::apic::{offset, register::ErrorStatus, ApicBackend, LocalApic};
+
+    use super::{ApStartup, Delay, StartupError, StartupStep};
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Event {
+        Icr(u64),
+        Delay(u64),
+    }
+
+    /// 记录所有写入 ICR 的命令，投递状态在被轮询 `pending_polls` 次后清除。
+    struct FakeIcr<'a> {
+        log: &'a RefCell<Vec<Event>>,
+        icr: u64,
+        esr: u32,
+        pending_polls: u32,
+        polls: core::cell::Cell<u32>,
+    }
+
+    impl<'a> ApicBackend for FakeIcr<'a> {
+        const X2APIC: bool = false;
+
+        fn read(&self, offset: u32) -> u32 {
+            match offset {
+                offset::ESR => self.esr,
+                offset::ICR_LOW => self.read_icr() as u32,
+                _ => 0,
+            }
+        }
+        fn write(&mut self, _offset: u32, _value: u32) {}
+        fn read_icr(&self) -> u64 {
+            let polls = self.polls.get();
+            self.polls.set(polls + 1);
+            if polls < self.pending_polls {
+                self.icr | 1 << 12
+            } else {
+                self.icr
+            }
+        }
+        fn write_icr(&mut self, value: u64) {
+            self.log.borrow_mut().push(Event::Icr(value));
+            self.icr = value;
+            self.polls.set(0);
+        }
+    }
+
+    struct FakeDelay<'a> {
+        log: &'a RefCell<Vec<Event>>,
+    }
+
+    impl<'a> Delay for FakeDelay<'a> {
+        fn delay_us(&mut self, us: u64) {
+            self.log.borrow_mut().push(Event::Delay(us));
+        }
+    }
+
+    fn fake(log: &RefCell<Vec<Event>>, pending_polls: u32, esr: u32) -> LocalApic<FakeIcr<'_>> {
+        LocalApic::new(FakeIcr {
+            log,
+            icr: 0,
+            esr,
+            pending_polls,
+            polls: core::cell::Cell::new(0),
+        })
+    }
+
+    #[test]
+    fn init_sipi_sipi() {
+        let log = RefCell::new(Vec::new());
+        let mut apic = fake(&log, 0, 0);
+        let mut delay = FakeDelay { log: &log };
+        ApStartup::new()
+            .start(&mut apic, &mut delay, 1, 0x08)
+            .unwrap();
+        assert_eq!(
+            log.into_inner(),
+            vec![
+                Event::Icr(0x0100_0000_0000_4500),
+                Event::Delay(10_000),
+                Event::Icr(0x0100_0000_0000_4608),
+                Event::Delay(200),
+                Event::Icr(0x0100_0000_0000_4608),
+                Event::Delay(200),
+            ]
+        );
+
+        // 投递状态需要轮询两次才清除，并发送 INIT de-assert
+        let log = RefCell::new(Vec::new());
+        let mut apic = fake(&log, 2, 0);
+        let mut delay = FakeDelay { log: &log };
+        ApStartup::new()
+            .with_init_deassert(true)
+            .start(&mut apic, &mut delay, 1, 0x08)
+            .unwrap();
+        assert_eq!(
+            &log.into_inner()[..5],
+            &[
+                Event::Icr(0x0100_0000_0000_4500),
+                Event::Delay(10),
+                Event::Delay(10),
+                Event::Icr(0x0100_0000_0000_8500),
+                Event::Delay(10),
+            ]
+        );
+    }
+
+    #[test]
+    fn startup_errors() {
+        let log = RefCell::new(Vec::new());
+        let mut delay = FakeDelay { log: &log };
+
+        let mut apic = fake(&log, u32::MAX, 0);
+        assert_eq!(
+            ApStartup::new()
+                .with_poll(10, 3)
+                .start(&mut apic, &mut delay, 1, 0x08),
+            Err(StartupError::DeliveryTimeout(StartupStep::Init))
+        );
+
+        let mut apic = fake(&log, 0, 0x40);
+        assert_eq!(
+            ApStartup::new().start(&mut apic, &mut delay, 1, 0x08),
+            Err(StartupError::DeliveryError(
+                StartupStep::FirstSipi,
+                ErrorStatus { data: 0x40 }
+            ))
+        );
+
+        let mut apic = fake(&log, 0, 0);
+        assert_eq!(
+            ApStartup::new().start(&mut apic, &mut delay, 0x100, 0x08),
+            Err(StartupError::InvalidDestination(0x100))
+        );
+        assert_eq!(
+            ApStartup::new().start(&mut apic, &mut delay, 1, 0xa0),
+            Err(StartupError::InvalidTrampoline(0xa0))
+        );
+    }
+}
@@ -0,0 +1,55 @@
+use crate::{
+    msr::{
+        apic::{ApicBaseBuffer, ApicMode},
+        MsrAccess,
+    },
+    Clean,
+};
+
+use super::{offset, ApicBackend};
+
+/// # x2APIC 后端
+///
+/// 寄存器映射到 MSR 800h-8FFh，地址为 `800h + (xAPIC 偏移 >> 4)`。
+/// ICR 合并为一个 64 bit 的 MSR（830h），DFR 不再存在，LDR 和 ID 均为只读。
+pub struct X2ApicBackend<A: MsrAccess> {
+    access: A,
+}
+
+impl<A: MsrAccess> X2ApicBackend<A> {
+    /// 本地 APIC 不处于 x2APIC 模式时返回 None，参见 [`switch_mode`](Clean::switch_mode)。
+    pub fn new(access: A, apic_base: &Clean<ApicBaseBuffer>) -> Option<Self> {
+        if apic_base.mode()? != ApicMode::X2Apic {
+            return None;
+        }
+        Some(Self { access })
+    }
+    pub fn into_inner(self) -> A {
+        self.access
+    }
+
+    /// xAPIC 偏移对应的 MSR 地址
+    pub const fn msr_addr(offset: u32) -> u32 {
+        0x800 + (offset >> 4)
+    }
+}
+
+impl<A: MsrAccess> ApicBackend for X2ApicBackend<A> {
+    const X2APIC: bool = true;
+
+    #[inline]
+    fn read(&self, offset: u32) -> u32 {
+        self.access.read_msr(Self::msr_addr(offset)) as u32
+    }
+    #[inline]
+    fn write(&mut self, offset: u32, value: u32) {
+        self.access.write_msr(Self::msr_addr(offset), value as u64)
+    }
+    fn read_icr(&self) -> u64 {
+        self.access.read_msr(Self::msr_addr(offset::ICR_LOW))
+    }
+    fn write_icr(&mut self, value: u64) {
+        self.access
+            .write_msr(Self::msr_addr(offset::ICR_LOW), value)
+    }
+}
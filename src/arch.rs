@@ -4,10 +4,10 @@ pub mod extension;
 use crate::cr::cr8::Cr8;
 
 use crate::{
-    cpuid::Cpuid,
+    cpuid::{Cpuid, CpuidSource},
     cr::{cr0::Cr0, cr2::Cr2, cr3::Cr3, cr4::Cr4},
     mem::segment::{cs::Cs, selector::Privilege},
-    msr::efer::Efer,
+    msr::{efer::Efer, Msr},
 };
 
 use self::extension::ArchExtension;
@@ -46,13 +46,20 @@ impl Arch {
     pub fn extension(&self) -> Option<ArchExtension> {
         let cpuid = Cpuid::inst()?;
         let std_feature = cpuid.std_feature();
-        let efer = Efer::inst(&std_feature)?;
+        let structured_ext_feature = cpuid.structured_ext_feature();
+        let ext_feature = cpuid.ext_feature();
+        // Msr::inst 函数中已经检查了特权情况
+        let msr = Msr::inst(&std_feature)?;
+        let efer = Efer::inst_checked(&std_feature, &ext_feature)?;
         let cr4 = Cr4::inst()?; // Cr4 中的大部分字段读写均需要参考 std_feature 或通过 Cpuid 来查询
 
         Some(ArchExtension {
             std_feature,
+            structured_ext_feature,
+            ext_feature,
             cpuid,
             efer,
+            msr,
             cr4,
         })
     }
@@ -1,22 +1,31 @@
 use crate::{
-    cpuid::{feature::StdFeature, Cpuid},
+    cpuid::{
+        feature::{ExtFeature, StdFeature, StructuredExtFeature},
+        Cpuid,
+    },
     cr::cr4::{Cr4, Cr4Buffer},
-    msr::efer::Efer,
+    msr::{efer::Efer, Msr},
     Clean,
 };
 
-/// 唯一占据空间的是 std_feature：8 个这字节，其余字段不占用空间。
+/// 占据空间的是 std_feature（12 字节）、structured_ext_feature 和 ext_feature，其余字段不占用空间。
 ///
 /// 构建本结构体的最直接方法：调用 `let arch_extension = Arch::init()?.extension()?`
 pub struct ArchExtension {
     pub std_feature: StdFeature,
+    pub structured_ext_feature: StructuredExtFeature,
+    pub ext_feature: ExtFeature,
     pub cpuid: Cpuid,
     pub efer: Efer,
+    pub msr: Msr,
     pub cr4: Cr4,
 }
 impl ArchExtension {
     pub fn pcid_extension(&self) -> Option<PcidExtension> {
-        if self.efer.buffer()?.long_mode_activated() && self.std_feature.support_pcid() {
+        if self.ext_feature.support_long_mode()
+            && self.efer.buffer()?.long_mode_activated()
+            && self.std_feature.support_pcid()
+        {
             Some(PcidExtension {
                 cr4_buffer: self.cr4.buffer()?,
             })
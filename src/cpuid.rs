@@ -1,10 +1,38 @@
+pub mod address;
+pub mod cache;
+pub mod encryption;
 pub mod feature;
+pub mod hypervisor;
+pub mod leaf;
+pub mod perfmon;
+pub mod requirement;
+pub mod signature;
+pub mod snapshot;
+pub mod table;
+pub mod timing;
+pub mod topology;
+pub mod vendor;
+pub mod xsave;
 
 use core::marker::PhantomData;
 
-use self::feature::StdFeature;
+use self::{
+    address::AddressWidths,
+    cache::{AmdTlbInfo, CacheIter},
+    encryption::MemEncryption,
+    feature::{ExtFeature, StdFeature, StructuredExtFeature},
+    hypervisor::{HyperVFeature, Hypervisor, HypervisorVendor, KvmFeature},
+    leaf::CpuidError,
+    perfmon::PerfMonInfo,
+    requirement::{FeatureSet, RequirementReport},
+    signature::CpuSignature,
+    timing::TscInfo,
+    topology::Topology,
+    vendor::{BrandString, Vendor},
+    xsave::XsaveInfo,
+};
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct CpuidResult {
     pub eax: u32,
     pub ebx: u32,
@@ -92,26 +120,219 @@ impl Cpuid {
             edx: edx as u32,
         }
     }
+}
+
+impl CpuidSource for Cpuid {
+    #[inline]
+    fn query(&self, leaf: usize, sub_leaf: usize) -> CpuidResult {
+        Cpuid::query(self, leaf, sub_leaf)
+    }
+}
+
+/// # CPUID 数据来源
+///
+/// 所有的解码函数均以 provided method 的形式定义在本 trait 中，
+/// 既可以作用于 [`Cpuid`]（直接执行 cpuid 指令），
+/// 也可以作用于 [`CpuidTable`](table::CpuidTable)（事先记录的数据，用于在宿主机上测试）。
+pub trait CpuidSource {
+    /// 根据功能号和子功能号查询处理器信息。
+    ///
+    /// ❗ 本函数不检查功能号是否受支持，解码时应使用 [`query_checked`](Self::query_checked)。
+    fn query(&self, leaf: usize, sub_leaf: usize) -> CpuidResult;
+
+    /// 查询前检查功能号和子功能号是否受支持，详见 [`leaf`] 模块。
+    fn query_checked(&self, leaf: u32, sub_leaf: u32) -> Result<CpuidResult, CpuidError> {
+        leaf::query_checked(self, leaf, sub_leaf)
+    }
 
-    pub fn std_feature(&self) -> StdFeature {
-        let result = self.query(0x01, 0);
+    /// 所支持的最大基本功能号，即 `CPUID.(EAX=0h):EAX`。
+    fn max_basic_leaf(&self) -> u32 {
+        leaf::max_basic_leaf(self)
+    }
+
+    /// 所支持的最大扩展功能号，即 `CPUID.(EAX=8000_0000h):EAX`。
+    ///
+    /// 若处理器不支持任何扩展功能号，则返回 0。
+    fn max_ext_leaf(&self) -> u32 {
+        leaf::max_leaf_in_range(self, 0x8000_0000)
+    }
+
+    /// 4000_0000h 处虚拟机监视器接口所支持的最大功能号，若未运行在虚拟机监视器之上，则返回 None。
+    fn max_hypervisor_leaf(&self) -> Option<u32> {
+        self.hypervisor().map(|hypervisor| hypervisor.max_leaf())
+    }
+
+    /// 读取 01h 功能号
+    fn std_feature(&self) -> StdFeature {
+        let result = self.query_checked(0x01, 0).unwrap_or_default();
         StdFeature {
+            ebx: result.ebx,
             ecx: result.ecx,
             edx: result.edx,
         }
     }
+
+    /// 读取 07h 功能号下的所有子功能号，若处理器不支持 07h 功能号，则所有特性位均为 0。
+    fn structured_ext_feature(&self) -> StructuredExtFeature {
+        let mut feature = StructuredExtFeature::default();
+        let result = match self.query_checked(0x07, 0) {
+            Ok(result) => result,
+            Err(_) => return feature,
+        };
+        feature.max_sub_leaf = result.eax;
+        feature.ebx = result.ebx;
+        feature.ecx = result.ecx;
+        feature.edx = result.edx;
+        if let Ok(result) = self.query_checked(0x07, 1) {
+            feature.eax1 = result.eax;
+            feature.edx1 = result.edx;
+        }
+        if let Ok(result) = self.query_checked(0x07, 2) {
+            feature.edx2 = result.edx;
+        }
+        feature
+    }
+
+    /// 读取 8000_0001h 功能号，若处理器不支持该功能号，则所有特性位均为 0。
+    fn ext_feature(&self) -> ExtFeature {
+        let result = self.query_checked(0x8000_0001, 0).unwrap_or_default();
+        ExtFeature {
+            ecx: result.ecx,
+            edx: result.edx,
+        }
+    }
+
+    /// 处理器制造商，由 `CPUID.(EAX=0h)` 的 EBX、EDX、ECX 决定。
+    fn vendor(&self) -> Vendor {
+        let result = self.query(0x00, 0);
+        Vendor::from_regs(result.ebx, result.edx, result.ecx)
+    }
+
+    /// 处理器的 family、model 和 stepping。
+    fn signature(&self) -> CpuSignature {
+        CpuSignature::from_raw(self.query_checked(0x01, 0).unwrap_or_default().eax)
+    }
+
+    /// 处理器商标字符串，若处理器不支持 8000_0004h 功能号，则返回 None。
+    fn brand_string(&self) -> Option<BrandString> {
+        let mut bytes = [0u8; 48];
+        for (i, leaf) in (0x8000_0002..=0x8000_0004).enumerate() {
+            let result = self.query_checked(leaf, 0).ok()?;
+            for (j, reg) in [result.eax, result.ebx, result.ecx, result.edx]
+                .iter()
+                .enumerate()
+            {
+                let offset = i * 16 + j * 4;
+                bytes[offset..offset + 4].copy_from_slice(&reg.to_le_bytes());
+            }
+        }
+        Some(BrandString { bytes })
+    }
+
+    /// 遍历所有缓存，Intel 处理器使用 04h 功能号，AMD 处理器使用 8000_001Dh 功能号。
+    ///
+    /// 若处理器不支持对应的功能号，则不会返回任何缓存描述符。
+    fn caches(&self) -> CacheIter<'_, Self> {
+        CacheIter::new(self)
+    }
+
+    /// AMD 处理器的 L1、L2 TLB 信息，若不是 AMD 兼容的处理器或不支持 8000_0006h 功能号，则返回 None。
+    fn amd_tlb_info(&self) -> Option<AmdTlbInfo> {
+        if !self.vendor().is_amd_compatible() {
+            return None;
+        }
+        Some(AmdTlbInfo::from_results(
+            &self.query_checked(0x8000_0005, 0).ok()?,
+            &self.query_checked(0x8000_0006, 0).ok()?,
+        ))
+    }
+
+    /// 处理器拓扑，用于将 APIC ID 拆分为 package、die、core 和 thread。
+    fn topology(&self) -> Topology {
+        Topology::from_cpuid(self)
+    }
+
+    /// 当前逻辑处理器的 APIC ID。
+    ///
+    /// 支持 0Bh 功能号时返回 32 位的 x2APIC ID，否则返回 `CPUID.(EAX=01h):EBX[31:24]` 中的 8 位初始 APIC ID。
+    fn apic_id(&self) -> u32 {
+        if let Ok(result) = self.query_checked(0x0b, 0) {
+            if result.ebx & 0xffff != 0 {
+                return result.edx;
+            }
+        }
+        self.std_feature().initial_apic_id() as u32
+    }
+
+    /// XSAVE 状态组件信息，若处理器不支持 XSAVE 特性，则返回 None。
+    fn xsave_info(&self) -> Option<XsaveInfo> {
+        if self.query_checked(0x0d, 0).is_err() || !self.std_feature().support_xsave() {
+            return None;
+        }
+        Some(XsaveInfo::from_cpuid(self))
+    }
+
+    /// 物理地址、线性地址宽度
+    fn address_widths(&self) -> AddressWidths {
+        AddressWidths::from_cpuid(self)
+    }
+
+    /// AMD 内存加密（SME/SEV）能力，若不是 AMD 兼容的处理器或不支持 8000_001Fh 功能号，则返回 None。
+    fn mem_encryption(&self) -> Option<MemEncryption> {
+        MemEncryption::from_cpuid(self)
+    }
+
+    /// 性能计数器的数量与位宽，若处理器不支持架构性能监控，则返回 None。
+    fn perfmon_info(&self) -> Option<PerfMonInfo> {
+        PerfMonInfo::from_cpuid(self)
+    }
+
+    /// TSC 频率与不变 TSC 特性
+    fn tsc_info(&self) -> TscInfo {
+        TscInfo::from_cpuid(self)
+    }
+
+    /// 检查 `CPUID.(EAX=01h):ECX[31]`，若运行在虚拟机监视器之上，则返回 4000_0000h 处的虚拟机监视器接口。
+    fn hypervisor(&self) -> Option<Hypervisor> {
+        Hypervisor::detect(self)
+    }
+
+    /// KVM 半虚拟化特性，若未运行在 KVM 之上，则返回 None。
+    fn kvm_feature(&self) -> Option<KvmFeature> {
+        let hypervisor = Hypervisor::find(self, HypervisorVendor::Kvm)?;
+        KvmFeature::from_hypervisor(self, &hypervisor)
+    }
+
+    /// Hyper-V 分区特权与特性，若虚拟机监视器未提供 Hyper-V 接口，则返回 None。
+    fn hyperv_feature(&self) -> Option<HyperVFeature> {
+        let hypervisor = Hypervisor::find(self, HypervisorVendor::HyperV)?;
+        HyperVFeature::from_hypervisor(self, &hypervisor)
+    }
+
+    /// 检查处理器是否满足所声明的特性集合，返回缺少的特性。
+    fn check_requirements(&self, required: FeatureSet) -> RequirementReport {
+        required.check(self)
+    }
 }
 
 #[cfg(test)]
 mod test {
     use std::println;
 
-    use super::Cpuid;
+    use super::{Cpuid, CpuidSource};
 
     #[test]
     fn feature_test() {
         if let Some(cpuid) = Cpuid::inst() {
             println!("{:#}", cpuid.std_feature());
+            println!("{:?}", cpuid.structured_ext_feature());
+            println!("{:#x} {:?}", cpuid.max_ext_leaf(), cpuid.ext_feature());
+            println!(
+                "{} {:?} {}",
+                cpuid.vendor(),
+                cpuid.brand_string(),
+                cpuid.signature()
+            );
         }
     }
 }
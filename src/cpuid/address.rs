@@ -0,0 +1,111 @@
+use super::CpuidSource;
+
+/// # 地址宽度
+///
+/// 由 `CPUID.(EAX=8000_0008h):EAX` 解码而来：
+///
+/// + `[7:0]`：物理地址宽度（MAXPHYADDR）；
+/// + `[15:8]`：线性地址宽度；
+/// + `[23:16]`：嵌套分页下客户机的物理地址宽度，为 0 时与物理地址宽度相同。
+///
+/// 处理器不支持 8000_0008h 功能号时，若支持 PAE 或 PSE-36，则物理地址宽度为 36 位，否则为 32 位；
+/// 线性地址宽度为 32 位。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AddressWidths {
+    pub(crate) physical: u8,
+    pub(crate) linear: u8,
+    pub(crate) guest_physical: u8,
+}
+
+impl AddressWidths {
+    pub(crate) fn from_cpuid<S: CpuidSource + ?Sized>(cpuid: &S) -> Self {
+        if let Ok(result) = cpuid.query_checked(0x8000_0008, 0) {
+            let eax = result.eax;
+            return Self {
+                physical: eax as u8,
+                linear: (eax >> 8) as u8,
+                guest_physical: (eax >> 16) as u8,
+            };
+        }
+        let std_feature = cpuid.std_feature();
+        let physical = if std_feature.support_pae() || std_feature.support_pse36() {
+            36
+        } else {
+            32
+        };
+        Self {
+            physical,
+            linear: 32,
+            guest_physical: 0,
+        }
+    }
+
+    /// 物理地址宽度，即 MAXPHYADDR
+    pub fn physical(&self) -> u8 {
+        self.physical
+    }
+    /// 线性地址宽度
+    pub fn linear(&self) -> u8 {
+        self.linear
+    }
+    /// 客户机物理地址宽度
+    pub fn guest_physical(&self) -> u8 {
+        if self.guest_physical == 0 {
+            self.physical
+        } else {
+            self.guest_physical
+        }
+    }
+    /// 物理地址中所有有效 bit 组成的掩码
+    pub fn physical_mask(&self) -> u64 {
+        if self.physical >= 64 {
+            u64::MAX
+        } else {
+            (1 << self.physical) - 1
+        }
+    }
+    /// 物理地址是否超出 MAXPHYADDR；超出部分属于保留位，写入页表或 CR3、MTRR 等寄存器后会导致 #GP 或 #PF 异常。
+    pub fn is_valid_physical(&self, addr: u64) -> bool {
+        addr & !self.physical_mask() == 0
+    }
+    /// 线性地址是否是规范地址，即 `[63:linear]` 均与第 `linear - 1` bit 相同。
+    pub fn is_canonical(&self, addr: u64) -> bool {
+        if self.linear == 0 || self.linear >= 64 {
+            return true;
+        }
+        let shift = 64 - self.linear as u32;
+        (((addr << shift) as i64) >> shift) as u64 == addr
+    }
+}
+
+#[cfg(test)]
+impl AddressWidths {
+    /// 测试使用的地址宽度：39 bit 物理地址，48 bit 线性地址
+    pub(crate) const TEST: AddressWidths = AddressWidths {
+        physical: 39,
+        linear: 48,
+        guest_physical: 0,
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::AddressWidths;
+
+    #[test]
+    fn address_validation() {
+        let widths = AddressWidths {
+            physical: 39,
+            linear: 48,
+            guest_physical: 0,
+        };
+        assert_eq!(widths.guest_physical(), 39);
+        assert_eq!(widths.physical_mask(), 0x7f_ffff_ffff);
+        assert!(widths.is_valid_physical(0x7f_ffff_f000));
+        assert!(!widths.is_valid_physical(0x80_0000_0000));
+        assert!(widths.is_canonical(0x0000_7fff_ffff_ffff));
+        assert!(widths.is_canonical(0xffff_8000_0000_0000));
+        assert!(!widths.is_canonical(0x0000_8000_0000_0000));
+        assert!(!widths.is_canonical(0xfff0_0000_0000_0000));
+    }
+}
@@ -0,0 +1,341 @@
+use super::{vendor::Vendor, CpuidResult, CpuidSource};
+
+/// 缓存类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheType {
+    Data,
+    Instruction,
+    Unified,
+}
+
+/// # 缓存描述符
+///
+/// Intel `CPUID.(EAX=04h, ECX=n)` 和 AMD `CPUID.(EAX=8000_001Dh, ECX=n)` 的格式一致，
+/// 每个子功能号描述一个缓存。
+#[derive(Debug, Clone, Copy)]
+pub struct CacheDescriptor {
+    pub(crate) eax: u32,
+    pub(crate) ebx: u32,
+    pub(crate) ecx: u32,
+    pub(crate) edx: u32,
+}
+
+impl CacheDescriptor {
+    pub(crate) fn from_result(result: &CpuidResult) -> Self {
+        Self {
+            eax: result.eax,
+            ebx: result.ebx,
+            ecx: result.ecx,
+            edx: result.edx,
+        }
+    }
+    /// 类型字段为 0 时表示已无更多缓存。
+    pub fn cache_type(&self) -> Option<CacheType> {
+        match self.read_type() {
+            1 => Some(CacheType::Data),
+            2 => Some(CacheType::Instruction),
+            3 => Some(CacheType::Unified),
+            _ => None,
+        }
+    }
+    /// 缓存行大小（字节）
+    pub fn line_size(&self) -> u32 {
+        self.read_line_size() as u32 + 1
+    }
+    pub fn partitions(&self) -> u32 {
+        self.read_partitions() as u32 + 1
+    }
+    /// 相联路数，全相联时等于缓存行数。
+    pub fn ways(&self) -> u32 {
+        self.read_ways() as u32 + 1
+    }
+    pub fn sets(&self) -> u32 {
+        self.ecx + 1
+    }
+    /// 缓存总大小（字节）
+    pub fn size(&self) -> u64 {
+        self.ways() as u64 * self.partitions() as u64 * self.line_size() as u64 * self.sets() as u64
+    }
+    /// 共享该缓存的最大逻辑处理器数量
+    pub fn sharing_threads(&self) -> u32 {
+        self.read_sharing_threads() as u32 + 1
+    }
+}
+
+impl_reg_buffer_trait!(CacheDescriptor);
+
+plain_field! {
+    CacheDescriptor {
+        read_type:                  fields::Type,
+        pub level:                  fields::Level,
+        pub self_initializing:      fields::SelfInitializing,
+        pub fully_associative:      fields::FullyAssociative,
+        read_sharing_threads:       fields::SharingThreads,
+        read_line_size:             fields::LineSize,
+        read_partitions:            fields::Partitions,
+        read_ways:                  fields::Ways,
+        pub wbinvd_not_inclusive:   fields::WbinvdNotInclusive,
+        pub inclusive:              fields::Inclusive,
+        pub complex_indexing:       fields::ComplexIndexing,
+    }
+}
+
+pub mod fields {
+    use super::CacheDescriptor;
+
+    bits::fields_ex! {
+        CacheDescriptor [eax] {
+            pub Type                [00..=04, ro, u8],
+            /// 缓存级别，从 1 开始
+            pub Level               [05..=07, ro, u8],
+            /// 无需软件初始化
+            pub SelfInitializing    [08, ro, bool],
+            pub FullyAssociative    [09, ro, bool],
+            /// 共享该缓存的最大逻辑处理器数量减 1
+            pub SharingThreads      [14..=25, ro, u16],
+        }
+        CacheDescriptor [ebx] {
+            /// 缓存行大小减 1
+            pub LineSize            [00..=11, ro, u16],
+            /// 物理行分区数减 1
+            pub Partitions          [12..=21, ro, u16],
+            /// 相联路数减 1
+            pub Ways                [22..=31, ro, u16],
+        }
+        CacheDescriptor [edx] {
+            /// WBINVD/INVD 不保证会作用于共享该缓存的其他逻辑处理器的下级缓存
+            pub WbinvdNotInclusive  [00, ro, bool],
+            /// 包含下级缓存
+            pub Inclusive           [01, ro, bool],
+            /// 使用复杂的函数来索引缓存，而不是直接使用地址位
+            pub ComplexIndexing     [02, ro, bool],
+        }
+    }
+}
+
+/// 依次遍历缓存描述符，直到遇到类型为 0 的子功能号。
+pub struct CacheIter<'a, S: CpuidSource + ?Sized> {
+    cpuid: &'a S,
+    leaf: u32,
+    sub_leaf: u32,
+}
+
+impl<'a, S: CpuidSource + ?Sized> CacheIter<'a, S> {
+    /// 防止模拟器返回错误的数据而导致无限遍历
+    const MAX_SUB_LEAF: u32 = 64;
+
+    pub(crate) fn new(cpuid: &'a S) -> Self {
+        let leaf = match cpuid.vendor() {
+            Vendor::Amd | Vendor::Hygon => {
+                if cpuid.ext_feature().support_topology_ext() {
+                    0x8000_001d
+                } else {
+                    0
+                }
+            }
+            _ => {
+                if cpuid.max_basic_leaf() >= 0x04 {
+                    0x04
+                } else {
+                    0
+                }
+            }
+        };
+        Self {
+            cpuid,
+            leaf,
+            sub_leaf: 0,
+        }
+    }
+}
+
+impl<'a, S: CpuidSource + ?Sized> Iterator for CacheIter<'a, S> {
+    type Item = CacheDescriptor;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.leaf == 0 || self.sub_leaf >= Self::MAX_SUB_LEAF {
+            return None;
+        }
+        // 缓存类型为 0 的子功能号无效
+        let descriptor =
+            CacheDescriptor::from_result(&self.cpuid.query_checked(self.leaf, self.sub_leaf).ok()?);
+        descriptor.cache_type()?;
+        self.sub_leaf += 1;
+        Some(descriptor)
+    }
+}
+
+/// TLB 或缓存的相联度
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Associativity {
+    Disabled,
+    DirectMapped,
+    Ways(u16),
+    Full,
+    /// 保留的编码；8000_0006h 中编码 9 表示需要通过 8000_001Dh 功能号获取。
+    Reserved(u8),
+}
+
+impl Associativity {
+    /// 8000_0005h 中的 8 bit 编码
+    fn from_l1(data: u8) -> Self {
+        match data {
+            0x00 => Associativity::Reserved(0),
+            0x01 => Associativity::DirectMapped,
+            0xff => Associativity::Full,
+            ways => Associativity::Ways(ways as u16),
+        }
+    }
+    /// 8000_0006h 中的 4 bit 编码
+    fn from_l2(data: u8) -> Self {
+        match data {
+            0x0 => Associativity::Disabled,
+            0x1 => Associativity::DirectMapped,
+            0x2 => Associativity::Ways(2),
+            0x3 => Associativity::Ways(3),
+            0x4 => Associativity::Ways(4),
+            0x5 => Associativity::Ways(6),
+            0x6 => Associativity::Ways(8),
+            0x8 => Associativity::Ways(16),
+            0xa => Associativity::Ways(32),
+            0xb => Associativity::Ways(48),
+            0xc => Associativity::Ways(64),
+            0xd => Associativity::Ways(96),
+            0xe => Associativity::Ways(128),
+            0xf => Associativity::Full,
+            other => Associativity::Reserved(other),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tlb {
+    pub entries: u16,
+    pub associativity: Associativity,
+}
+
+impl Tlb {
+    fn from_l1(data: u16) -> Self {
+        Self {
+            entries: data & 0xff,
+            associativity: Associativity::from_l1((data >> 8) as u8),
+        }
+    }
+    fn from_l2(data: u16) -> Self {
+        Self {
+            entries: data & 0x0fff,
+            associativity: Associativity::from_l2((data >> 12) as u8),
+        }
+    }
+}
+
+/// 指令 TLB 和数据 TLB
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TlbPair {
+    pub instruction: Tlb,
+    pub data: Tlb,
+}
+
+/// # AMD TLB 信息
+///
+/// 由 `CPUID.(EAX=8000_0005h)` 和 `CPUID.(EAX=8000_0006h)` 的 EAX、EBX 解码而来。
+/// 其中 2M 页的 TLB 同时用于 4M 页，每个 4M 页占用两个条目。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AmdTlbInfo {
+    pub l1_4k: TlbPair,
+    pub l1_2m: TlbPair,
+    pub l2_4k: TlbPair,
+    pub l2_2m: TlbPair,
+}
+
+impl AmdTlbInfo {
+    pub(crate) fn from_results(l1: &CpuidResult, l2: &CpuidResult) -> Self {
+        let l1_pair = |reg: u32| TlbPair {
+            instruction: Tlb::from_l1(reg as u16),
+            data: Tlb::from_l1((reg >> 16) as u16),
+        };
+        let l2_pair = |reg: u32| TlbPair {
+            instruction: Tlb::from_l2(reg as u16),
+            data: Tlb::from_l2((reg >> 16) as u16),
+        };
+        Self {
+            l1_4k: l1_pair(l1.ebx),
+            l1_2m: l1_pair(l1.eax),
+            l2_4k: l2_pair(l2.ebx),
+            l2_2m: l2_pair(l2.eax),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::println;
+
+    use crate::cpuid::{Cpuid, CpuidResult, CpuidSource};
+
+    use super::{AmdTlbInfo, Associativity, CacheDescriptor, CacheType, Tlb};
+
+    #[test]
+    fn cache_test() {
+        if let Some(cpuid) = Cpuid::inst() {
+            for cache in cpuid.caches() {
+                println!(
+                    "L{} {:?} {} bytes, shared by {}",
+                    cache.level(),
+                    cache.cache_type(),
+                    cache.size(),
+                    cache.sharing_threads()
+                );
+            }
+            println!("{:?}", cpuid.amd_tlb_info());
+        }
+    }
+
+    #[test]
+    fn descriptor_decode() {
+        // 48KB 12-way L1D，64 字节缓存行，2 个逻辑处理器共享
+        let cache = CacheDescriptor::from_result(&CpuidResult {
+            eax: 0x1c00_4121,
+            ebx: 0x02c0_003f,
+            ecx: 0x0000_003f,
+            edx: 0x0000_0000,
+        });
+        assert_eq!(cache.cache_type(), Some(CacheType::Data));
+        assert_eq!(cache.level(), 1);
+        assert_eq!(cache.line_size(), 64);
+        assert_eq!(cache.ways(), 12);
+        assert_eq!(cache.sets(), 64);
+        assert_eq!(cache.size(), 48 * 1024);
+        assert_eq!(cache.sharing_threads(), 2);
+    }
+
+    #[test]
+    fn amd_tlb_decode() {
+        let l1 = CpuidResult {
+            eax: 0xff40_ff40,
+            ebx: 0xff40_ff40,
+            ecx: 0,
+            edx: 0,
+        };
+        let l2 = CpuidResult {
+            eax: 0x2200_2200,
+            ebx: 0x5800_0000 | 0x6800,
+            ecx: 0,
+            edx: 0,
+        };
+        let tlb = AmdTlbInfo::from_results(&l1, &l2);
+        assert_eq!(
+            tlb.l1_4k.data,
+            Tlb {
+                entries: 64,
+                associativity: Associativity::Full
+            }
+        );
+        assert_eq!(tlb.l2_2m.data.entries, 0x200);
+        assert_eq!(tlb.l2_2m.data.associativity, Associativity::Ways(2));
+        assert_eq!(tlb.l2_4k.data.entries, 0x800);
+        assert_eq!(tlb.l2_4k.data.associativity, Associativity::Ways(6));
+        assert_eq!(tlb.l2_4k.instruction.associativity, Associativity::Ways(8));
+        assert_eq!(tlb.l2_4k.instruction.entries, 0x800);
+    }
+}
@@ -0,0 +1,204 @@
+use super::{address::AddressWidths, CpuidResult, CpuidSource};
+
+/// # AMD 内存加密能力
+///
+/// 由 `CPUID.(EAX=8000_001Fh)` 解码而来：
+///
+/// + EAX：SME、SEV、SEV-ES、SEV-SNP 等特性位；
+/// + EBX：`[5:0]` 页表项中 C-bit 的位置，`[11:6]` 使能内存加密后物理地址宽度的减少量，
+///   `[15:12]` SEV-SNP 所支持的 VMPL 数量；
+/// + ECX：可同时运行的加密客户机数量；
+/// + EDX：SEV 客户机（未使能 SEV-ES）可使用的最小 ASID。
+///
+/// ❗ SME 还需要通过 [`Syscfg`](crate::msr::syscfg::Syscfg) 的 `MEME` 使能，
+/// 此后 C-bit 为 1 的页才会被加密。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemEncryption {
+    pub(crate) eax: u32,
+    pub(crate) ebx: u32,
+    pub(crate) ecx: u32,
+    pub(crate) edx: u32,
+}
+
+impl MemEncryption {
+    /// 若不是 AMD 兼容的处理器或不支持 8000_001Fh 功能号，则返回 None。
+    pub(crate) fn from_cpuid<S: CpuidSource + ?Sized>(cpuid: &S) -> Option<Self> {
+        if !cpuid.vendor().is_amd_compatible() {
+            return None;
+        }
+        Some(Self::from_result(
+            &cpuid.query_checked(0x8000_001f, 0).ok()?,
+        ))
+    }
+    pub(crate) fn from_result(result: &CpuidResult) -> Self {
+        Self {
+            eax: result.eax,
+            ebx: result.ebx,
+            ecx: result.ecx,
+            edx: result.edx,
+        }
+    }
+
+    /// 可同时运行的加密客户机数量
+    pub fn encrypted_guests(&self) -> u32 {
+        self.ecx
+    }
+    /// 未使能 SEV-ES 的 SEV 客户机可使用的最小 ASID，小于该值的 ASID 只能用于 SEV-ES 客户机。
+    pub fn min_sev_asid(&self) -> u32 {
+        self.edx
+    }
+
+    /// 需要 OR 进页表项物理地址中的加密掩码，即 `1 << C-bit`。
+    ///
+    /// 不支持 SME 和 SEV 时返回 0，此时 OR 该掩码不会产生任何影响。
+    pub fn encryption_mask(&self) -> u64 {
+        if !self.support_sme() && !self.support_sev() {
+            return 0;
+        }
+        match self.c_bit() {
+            0 => 0,
+            bit => 1 << bit,
+        }
+    }
+    /// 使能内存加密后的物理地址宽度。
+    ///
+    /// C-bit 及被保留的高位不能再用作地址位，因此在校验页表、CR3 中的地址时应使用本函数的结果。
+    pub fn reduced_physical_width(&self, widths: &AddressWidths) -> u8 {
+        widths
+            .physical()
+            .saturating_sub(self.physical_address_reduction())
+    }
+}
+
+impl_reg_buffer_trait!(MemEncryption);
+
+plain_field! {
+    MemEncryption {
+        /// 安全内存加密（Secure Memory Encryption）
+        pub support_sme:                fields::SME,
+        /// 安全加密虚拟化（Secure Encrypted Virtualization）
+        pub support_sev:                fields::SEV,
+        pub support_page_flush_msr:     fields::PageFlushMsr,
+        /// SEV 加密状态，客户机的寄存器状态同样被加密
+        pub support_sev_es:             fields::SevEs,
+        /// SEV 安全嵌套分页
+        pub support_sev_snp:            fields::SevSnp,
+        pub support_vmpl:               fields::Vmpl,
+        pub support_secure_tsc:         fields::SecureTsc,
+        /// 硬件保证不同加密域之间的缓存一致性，无需在修改加密属性时刷新缓存
+        pub coherency_enforced:         fields::HwEnfCacheCoh,
+        /// SEV 客户机只能运行在 64 bit 宿主机上
+        pub require_64bit_host:         fields::Req64BitHost,
+        pub support_vmsa_protection:    fields::VmsaRegProt,
+        /// 页表项中 C-bit 的位置
+        pub c_bit:                      fields::CBitPosition,
+        /// 使能内存加密后物理地址宽度的减少量
+        pub physical_address_reduction: fields::PhysAddrReduction,
+        /// SEV-SNP 所支持的 VMPL 数量
+        pub vmpl_count:                 fields::NumVmpl,
+    }
+}
+
+pub mod fields {
+    use super::MemEncryption;
+
+    bits::fields_ex! {
+        MemEncryption [eax] {
+            pub SME                 [00, ro, bool],
+            pub SEV                 [01, ro, bool],
+            /// 支持 `VM_PAGE_FLUSH` MSR，用于刷新指定加密页的缓存
+            pub PageFlushMsr        [02, ro, bool],
+            pub SevEs               [03, ro, bool],
+            pub SevSnp              [04, ro, bool],
+            /// 虚拟机特权级（Virtual Machine Privilege Level）
+            pub Vmpl                [05, ro, bool],
+            pub RmpQuery            [06, ro, bool],
+            pub VmplSss             [07, ro, bool],
+            pub SecureTsc           [08, ro, bool],
+            pub TscAuxVirt          [09, ro, bool],
+            pub HwEnfCacheCoh       [10, ro, bool],
+            pub Req64BitHost        [11, ro, bool],
+            pub RestrictedInjection [12, ro, bool],
+            pub AlternateInjection  [13, ro, bool],
+            pub DebugVirt           [14, ro, bool],
+            pub PreventHostIbs      [15, ro, bool],
+            /// 虚拟透明加密（Virtual Transparent Encryption）
+            pub Vte                 [16, ro, bool],
+            pub VmsaRegProt         [24, ro, bool],
+        }
+        MemEncryption [ebx] {
+            pub CBitPosition        [00..=05, ro, u8],
+            pub PhysAddrReduction   [06..=11, ro, u8],
+            pub NumVmpl             [12..=15, ro, u8],
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cpuid::{address::AddressWidths, table::CpuidTable, CpuidResult, CpuidSource};
+
+    #[test]
+    fn sev_snp() {
+        let mut table = CpuidTable::new();
+        // AuthenticAMD
+        table
+            .insert(
+                0x00,
+                0,
+                CpuidResult {
+                    eax: 0x10,
+                    ebx: 0x6874_7541,
+                    ecx: 0x444d_4163,
+                    edx: 0x6974_6e65,
+                },
+            )
+            .unwrap();
+        table
+            .insert(
+                0x8000_0000,
+                0,
+                CpuidResult {
+                    eax: 0x8000_0023,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        table
+            .insert(
+                0x8000_001f,
+                0,
+                CpuidResult {
+                    eax: 0x0101_fdff,
+                    ebx: 0x0000_4173,
+                    ecx: 0x0000_01fd,
+                    edx: 0x0000_0064,
+                },
+            )
+            .unwrap();
+
+        let encryption = table.mem_encryption().unwrap();
+        assert!(encryption.support_sme() && encryption.support_sev());
+        assert!(encryption.support_sev_es() && encryption.support_sev_snp());
+        assert!(encryption.support_vmsa_protection());
+        assert_eq!(encryption.c_bit(), 51);
+        assert_eq!(encryption.encryption_mask(), 1 << 51);
+        assert_eq!(encryption.physical_address_reduction(), 5);
+        assert_eq!(encryption.vmpl_count(), 4);
+        assert_eq!(encryption.encrypted_guests(), 509);
+        assert_eq!(encryption.min_sev_asid(), 100);
+
+        let widths = AddressWidths {
+            physical: 48,
+            linear: 48,
+            guest_physical: 0,
+        };
+        assert_eq!(encryption.reduced_physical_width(&widths), 43);
+    }
+
+    #[test]
+    fn not_amd() {
+        let table = CpuidTable::parse(include_str!("testdata/coffee_lake.txt")).unwrap();
+        assert!(table.mem_encryption().is_none());
+    }
+}
@@ -1,29 +1,178 @@
 use core::fmt::Display;
 
-#[derive(Debug)]
+/// # 标准特性
+///
+/// 由 `CPUID.(EAX=01h)` 的 EBX、ECX、EDX 组成。
+#[derive(Debug, Default)]
 pub struct StdFeature {
+    pub(crate) ebx: u32,
     pub(crate) ecx: u32,
     pub(crate) edx: u32,
 }
 
+impl StdFeature {
+    /// 与 `/proc/cpuinfo` 中 flags 一致的名称，以及读取对应特性位的方法，先 EDX 后 ECX。
+    const FLAGS: [(&'static str, fn(&StdFeature) -> bool); 60] = [
+        ("fpu", StdFeature::support_fpu),
+        ("vme", StdFeature::support_vme),
+        ("de", StdFeature::support_de),
+        ("pse", StdFeature::support_pse),
+        ("tsc", StdFeature::support_tsc),
+        ("msr", StdFeature::support_msr),
+        ("pae", StdFeature::support_pae),
+        ("mce", StdFeature::support_mce),
+        ("cx8", StdFeature::support_cx8),
+        ("apic", StdFeature::support_apic),
+        ("sep", StdFeature::support_sep),
+        ("mtrr", StdFeature::support_mtrr),
+        ("pge", StdFeature::support_pge),
+        ("mca", StdFeature::support_mca),
+        ("cmov", StdFeature::support_cmov),
+        ("pat", StdFeature::support_pat),
+        ("pse36", StdFeature::support_pse36),
+        ("pn", StdFeature::support_psn),
+        ("clflush", StdFeature::support_clflush),
+        ("dts", StdFeature::support_ds),
+        ("acpi", StdFeature::support_acpi),
+        ("mmx", StdFeature::support_mmx),
+        ("fxsr", StdFeature::support_fxsr),
+        ("sse", StdFeature::support_sse),
+        ("sse2", StdFeature::support_sse2),
+        ("ss", StdFeature::support_ss),
+        ("ht", StdFeature::support_htt),
+        ("tm", StdFeature::support_tm),
+        ("pbe", StdFeature::support_pbe),
+        ("pni", StdFeature::support_sse3),
+        ("pclmulqdq", StdFeature::support_pclmulqdq),
+        ("dtes64", StdFeature::support_dtes64),
+        ("monitor", StdFeature::support_monitor),
+        ("ds_cpl", StdFeature::support_ds_cpl),
+        ("vmx", StdFeature::support_vmx),
+        ("smx", StdFeature::support_smx),
+        ("est", StdFeature::support_eist),
+        ("tm2", StdFeature::support_tm2),
+        ("ssse3", StdFeature::support_ssse3),
+        ("cid", StdFeature::support_cnxt_id),
+        ("sdbg", StdFeature::support_sdbg),
+        ("fma", StdFeature::support_fma),
+        ("cx16", StdFeature::support_cx16),
+        ("xtpr", StdFeature::support_xtpr),
+        ("pdcm", StdFeature::support_pdcm),
+        ("pcid", StdFeature::support_pcid),
+        ("dca", StdFeature::support_dca),
+        ("sse4_1", StdFeature::support_sse4_1),
+        ("sse4_2", StdFeature::support_sse4_2),
+        ("x2apic", StdFeature::support_x2apic),
+        ("movbe", StdFeature::support_movbe),
+        ("popcnt", StdFeature::support_popcnt),
+        ("tsc_deadline_timer", StdFeature::support_tsc_deadline),
+        ("aes", StdFeature::support_aes),
+        ("xsave", StdFeature::support_xsave),
+        ("osxsave", StdFeature::osxsave_enabled),
+        ("avx", StdFeature::support_avx),
+        ("f16c", StdFeature::support_f16c),
+        ("rdrand", StdFeature::support_rdrand),
+        ("hypervisor", StdFeature::hypervisor_present),
+    ];
+
+    /// CLFLUSH 指令刷新的缓存行大小（字节），仅在支持 CLFLUSH 时有效。
+    pub fn clflush_line_size(&self) -> u32 {
+        self.read_clflush_line_size() as u32 * 8
+    }
+
+    /// 依次返回所有已置位的特性名称，先 EDX 后 ECX，与 `/proc/cpuinfo` 的顺序一致。
+    pub fn flags(&self) -> impl Iterator<Item = &'static str> + '_ {
+        Self::FLAGS
+            .iter()
+            .filter(move |(_, support)| support(self))
+            .map(|(name, _)| *name)
+    }
+}
+
 impl Display for StdFeature {
     fn fmt(&self, fmt: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        writeln!(
-            fmt,
-            "MSR: {msr}\nSSE: {sse3}",
-            msr = self.support_msr(),
-            sse3 = self.support_sse3()
-        )
+        writeln!(fmt, "initial apicid\t: {}", self.initial_apic_id())?;
+        writeln!(fmt, "clflush size\t: {}", self.clflush_line_size())?;
+        writeln!(fmt, "siblings\t: {}", self.max_logical_processors())?;
+        fmt.write_str("flags\t\t:")?;
+        for flag in self.flags() {
+            write!(fmt, " {}", flag)?;
+        }
+        writeln!(fmt)
     }
 }
 impl_reg_buffer_trait!(StdFeature);
 
 plain_field! {
     StdFeature {
-        pub support_msr:    fields::MSR,
-        pub support_fpu:    fields::FPU,
-        pub support_sse3:   fields::SSE3,
-        pub support_pcid:   fields::PCID
+        pub brand_index:            fields::BrandIndex,
+        read_clflush_line_size:     fields::ClflushLineSize,
+        /// package 内可寻址的最大逻辑处理器 ID 数量，仅在 HTT 置位时有效。
+        pub max_logical_processors: fields::MaxLogicalProcessors,
+        /// 8 位的初始 APIC ID
+        pub initial_apic_id:        fields::InitialApicId,
+
+        pub support_sse3:           fields::SSE3,
+        pub support_pclmulqdq:      fields::PCLMULQDQ,
+        pub support_dtes64:         fields::DTES64,
+        pub support_monitor:        fields::MONITOR,
+        pub support_ds_cpl:         fields::DS_CPL,
+        pub support_vmx:            fields::VMX,
+        pub support_smx:            fields::SMX,
+        pub support_eist:           fields::EIST,
+        pub support_tm2:            fields::TM2,
+        pub support_ssse3:          fields::SSSE3,
+        pub support_cnxt_id:        fields::CNXT_ID,
+        pub support_sdbg:           fields::SDBG,
+        pub support_fma:            fields::FMA,
+        pub support_cx16:           fields::CX16,
+        pub support_xtpr:           fields::XTPR,
+        pub support_pdcm:           fields::PDCM,
+        pub support_pcid:           fields::PCID,
+        pub support_dca:            fields::DCA,
+        pub support_sse4_1:         fields::SSE4_1,
+        pub support_sse4_2:         fields::SSE4_2,
+        pub support_x2apic:         fields::X2APIC,
+        pub support_movbe:          fields::MOVBE,
+        pub support_popcnt:         fields::POPCNT,
+        pub support_tsc_deadline:   fields::TSC_DEADLINE,
+        pub support_aes:            fields::AES,
+        pub support_xsave:          fields::XSAVE,
+        pub osxsave_enabled:        fields::OSXSAVE,
+        pub support_avx:            fields::AVX,
+        pub support_f16c:           fields::F16C,
+        pub support_rdrand:         fields::RDRAND,
+        pub hypervisor_present:     fields::HYPERVISOR,
+
+        pub support_fpu:            fields::FPU,
+        pub support_vme:            fields::VME,
+        pub support_de:             fields::DE,
+        pub support_pse:            fields::PSE,
+        pub support_tsc:            fields::TSC,
+        pub support_msr:            fields::MSR,
+        pub support_pae:            fields::PAE,
+        pub support_mce:            fields::MCE,
+        pub support_cx8:            fields::CX8,
+        pub support_apic:           fields::APIC,
+        pub support_sep:            fields::SEP,
+        pub support_mtrr:           fields::MTRR,
+        pub support_pge:            fields::PGE,
+        pub support_mca:            fields::MCA,
+        pub support_cmov:           fields::CMOV,
+        pub support_pat:            fields::PAT,
+        pub support_pse36:          fields::PSE36,
+        pub support_psn:            fields::PSN,
+        pub support_clflush:        fields::CLFSH,
+        pub support_ds:             fields::DS,
+        pub support_acpi:           fields::ACPI,
+        pub support_mmx:            fields::MMX,
+        pub support_fxsr:           fields::FXSR,
+        pub support_sse:            fields::SSE,
+        pub support_sse2:           fields::SSE2,
+        pub support_ss:             fields::SS,
+        pub support_htt:            fields::HTT,
+        pub support_tm:             fields::TM,
+        pub support_pbe:            fields::PBE,
     }
 }
 
@@ -31,13 +180,380 @@ pub mod fields {
     use super::StdFeature;
 
     bits::fields_ex! {
-       StdFeature [ecx] {
-           pub SSE3 [00, ro, bool],
-           pub PCID [17, ro, bool]
-       }
-       StdFeature [edx] {
-           pub FPU [0, ro, bool],
-           pub MSR [5, ro, bool],
-       }
+        StdFeature [ebx] {
+            pub BrandIndex              [00..=07, ro, u8],
+            /// CLFLUSH 指令刷新的缓存行大小，以 8 字节为单位
+            pub ClflushLineSize         [08..=15, ro, u8],
+            pub MaxLogicalProcessors    [16..=23, ro, u8],
+            pub InitialApicId           [24..=31, ro, u8],
+        }
+        StdFeature [ecx] {
+            pub SSE3            [00, ro, bool],
+            pub PCLMULQDQ       [01, ro, bool],
+            pub DTES64          [02, ro, bool],
+            /// MONITOR/MWAIT 指令
+            pub MONITOR         [03, ro, bool],
+            pub DS_CPL          [04, ro, bool],
+            pub VMX             [05, ro, bool],
+            pub SMX             [06, ro, bool],
+            pub EIST            [07, ro, bool],
+            pub TM2             [08, ro, bool],
+            pub SSSE3           [09, ro, bool],
+            pub CNXT_ID         [10, ro, bool],
+            pub SDBG            [11, ro, bool],
+            pub FMA             [12, ro, bool],
+            /// CMPXCHG16B 指令
+            pub CX16            [13, ro, bool],
+            pub XTPR            [14, ro, bool],
+            /// IA32_PERF_CAPABILITIES 寄存器
+            pub PDCM            [15, ro, bool],
+            pub PCID            [17, ro, bool],
+            pub DCA             [18, ro, bool],
+            pub SSE4_1          [19, ro, bool],
+            pub SSE4_2          [20, ro, bool],
+            /// x2APIC 模式
+            pub X2APIC          [21, ro, bool],
+            pub MOVBE           [22, ro, bool],
+            pub POPCNT          [23, ro, bool],
+            /// local APIC 定时器支持 TSC-deadline 模式
+            pub TSC_DEADLINE    [24, ro, bool],
+            pub AES             [25, ro, bool],
+            /// 支持 XSAVE/XRSTOR/XSETBV/XGETBV 指令以及 XCR0 寄存器
+            pub XSAVE           [26, ro, bool],
+            /// 操作系统已通过 `CR4.OSXSAVE` 使能 XSAVE 特性
+            pub OSXSAVE         [27, ro, bool],
+            pub AVX             [28, ro, bool],
+            pub F16C            [29, ro, bool],
+            pub RDRAND          [30, ro, bool],
+            /// 运行在虚拟机监视器之上，此时可以查询 4000_0000h 开始的功能号
+            pub HYPERVISOR      [31, ro, bool],
+        }
+        StdFeature [edx] {
+            pub FPU             [00, ro, bool],
+            /// virtual-8086 模式扩展，可通过 `CR4.VME` 使能
+            pub VME             [01, ro, bool],
+            /// 调试扩展，可通过 `CR4.DE` 使能
+            pub DE              [02, ro, bool],
+            /// 4MB 大页，可通过 `CR4.PSE` 使能
+            pub PSE             [03, ro, bool],
+            pub TSC             [04, ro, bool],
+            /// RDMSR/WRMSR 指令
+            pub MSR             [05, ro, bool],
+            pub PAE             [06, ro, bool],
+            pub MCE             [07, ro, bool],
+            /// CMPXCHG8B 指令
+            pub CX8             [08, ro, bool],
+            /// 片上 local APIC
+            pub APIC            [09, ro, bool],
+            /// SYSENTER/SYSEXIT 指令
+            pub SEP             [11, ro, bool],
+            pub MTRR            [12, ro, bool],
+            /// 全局页，可通过 `CR4.PGE` 使能
+            pub PGE             [13, ro, bool],
+            pub MCA             [14, ro, bool],
+            pub CMOV            [15, ro, bool],
+            pub PAT             [16, ro, bool],
+            pub PSE36           [17, ro, bool],
+            pub PSN             [18, ro, bool],
+            pub CLFSH           [19, ro, bool],
+            pub DS              [21, ro, bool],
+            pub ACPI            [22, ro, bool],
+            pub MMX             [23, ro, bool],
+            /// FXSAVE/FXRSTOR 指令，可通过 `CR4.OSFXSR` 使能
+            pub FXSR            [24, ro, bool],
+            pub SSE             [25, ro, bool],
+            pub SSE2            [26, ro, bool],
+            pub SS              [27, ro, bool],
+            /// `EBX[23:16]` 有效
+            pub HTT             [28, ro, bool],
+            pub TM              [29, ro, bool],
+            pub PBE             [31, ro, bool],
+        }
+    }
+}
+
+/// # 结构化扩展特性
+/// Structured Extended Feature Flags
+///
+/// 由 `CPUID.(EAX=07h, ECX=n)` 的所有子功能号组成，其中：
+///
+/// + 子功能号 0 的 EAX 为所支持的最大子功能号，EBX、ECX、EDX 为特性位；
+/// + 子功能号 1 的 EAX、EDX 为特性位；
+/// + 子功能号 2 的 EDX 为特性位。
+///
+/// 不支持的子功能号，对应的特性位均为 0。
+#[derive(Debug, Default)]
+pub struct StructuredExtFeature {
+    pub(crate) max_sub_leaf: u32,
+    pub(crate) ebx: u32,
+    pub(crate) ecx: u32,
+    pub(crate) edx: u32,
+    pub(crate) eax1: u32,
+    pub(crate) edx1: u32,
+    pub(crate) edx2: u32,
+}
+
+impl StructuredExtFeature {
+    /// 07h 功能号所支持的最大子功能号
+    pub fn max_sub_leaf(&self) -> u32 {
+        self.max_sub_leaf
+    }
+}
+
+impl_reg_buffer_trait!(StructuredExtFeature);
+
+plain_field! {
+    StructuredExtFeature {
+        pub support_fsgsbase:   structured_fields::FSGSBASE,
+        pub support_smep:       structured_fields::SMEP,
+        pub support_smap:       structured_fields::SMAP,
+        pub support_invpcid:    structured_fields::INVPCID,
+        pub support_umip:       structured_fields::UMIP,
+        pub support_pku:        structured_fields::PKU,
+        pub support_cet_ss:     structured_fields::CET_SS,
+        pub support_cet_ibt:    structured_fields::CET_IBT,
+        pub support_la57:       structured_fields::LA57,
+        pub support_avx2:       structured_fields::AVX2,
+        pub support_avx512f:    structured_fields::AVX512F,
+    }
+}
+
+pub mod structured_fields {
+    use super::StructuredExtFeature;
+
+    bits::fields_ex! {
+        StructuredExtFeature [ebx] {
+            /// 支持 RDFSBASE/RDGSBASE/WRFSBASE/WRGSBASE 指令，可通过 `CR4.FSGSBASE` 使能。
+            pub FSGSBASE            [00, ro, bool],
+            pub TSC_ADJUST          [01, ro, bool],
+            pub SGX                 [02, ro, bool],
+            pub BMI1                [03, ro, bool],
+            pub HLE                 [04, ro, bool],
+            pub AVX2                [05, ro, bool],
+            pub FDP_EXCPTN_ONLY     [06, ro, bool],
+            /// Supervisor-Mode Execution Prevention，可通过 `CR4.SMEP` 使能。
+            pub SMEP                [07, ro, bool],
+            pub BMI2                [08, ro, bool],
+            pub ERMS                [09, ro, bool],
+            /// 支持 INVPCID 指令
+            pub INVPCID             [10, ro, bool],
+            pub RTM                 [11, ro, bool],
+            pub RDT_M               [12, ro, bool],
+            pub FPU_CSDS_DEPRECATED [13, ro, bool],
+            pub MPX                 [14, ro, bool],
+            pub RDT_A               [15, ro, bool],
+            pub AVX512F             [16, ro, bool],
+            pub AVX512DQ            [17, ro, bool],
+            pub RDSEED              [18, ro, bool],
+            pub ADX                 [19, ro, bool],
+            /// Supervisor-Mode Access Prevention，可通过 `CR4.SMAP` 使能。
+            pub SMAP                [20, ro, bool],
+            pub AVX512_IFMA         [21, ro, bool],
+            pub CLFLUSHOPT          [23, ro, bool],
+            pub CLWB                [24, ro, bool],
+            pub PT                  [25, ro, bool],
+            pub AVX512PF            [26, ro, bool],
+            pub AVX512ER            [27, ro, bool],
+            pub AVX512CD            [28, ro, bool],
+            pub SHA                 [29, ro, bool],
+            pub AVX512BW            [30, ro, bool],
+            pub AVX512VL            [31, ro, bool],
+        }
+        StructuredExtFeature [ecx] {
+            pub PREFETCHWT1         [00, ro, bool],
+            pub AVX512_VBMI         [01, ro, bool],
+            /// User-Mode Instruction Prevention，可通过 `CR4.UMIP` 使能。
+            pub UMIP                [02, ro, bool],
+            /// 用户态页保护键，可通过 `CR4.PKE` 使能。
+            pub PKU                 [03, ro, bool],
+            /// 操作系统已通过 `CR4.PKE` 使能保护键
+            pub OSPKE               [04, ro, bool],
+            pub WAITPKG             [05, ro, bool],
+            pub AVX512_VBMI2        [06, ro, bool],
+            /// CET 影子栈，可通过 `CR4.CET` 使能。
+            pub CET_SS              [07, ro, bool],
+            pub GFNI                [08, ro, bool],
+            pub VAES                [09, ro, bool],
+            pub VPCLMULQDQ          [10, ro, bool],
+            pub AVX512_VNNI         [11, ro, bool],
+            pub AVX512_BITALG       [12, ro, bool],
+            pub TME_EN              [13, ro, bool],
+            pub AVX512_VPOPCNTDQ    [14, ro, bool],
+            /// 5 级分页，可通过 `CR4.LA57` 使能。
+            pub LA57                [16, ro, bool],
+            /// 64 位模式下 BNDLDX/BNDSTX 指令所使用的 MAWAU 值
+            pub MAWAU               [17..=21, ro, u8],
+            pub RDPID               [22, ro, bool],
+            pub KL                  [23, ro, bool],
+            pub CLDEMOTE            [25, ro, bool],
+            pub MOVDIRI             [27, ro, bool],
+            pub MOVDIR64B           [28, ro, bool],
+            pub ENQCMD              [29, ro, bool],
+            pub SGX_LC              [30, ro, bool],
+            pub PKS                 [31, ro, bool],
+        }
+        StructuredExtFeature [edx] {
+            pub AVX512_4VNNIW       [02, ro, bool],
+            pub AVX512_4FMAPS       [03, ro, bool],
+            pub FSRM                [04, ro, bool],
+            pub UINTR               [05, ro, bool],
+            pub AVX512_VP2INTERSECT [08, ro, bool],
+            pub MD_CLEAR            [10, ro, bool],
+            pub SERIALIZE           [14, ro, bool],
+            pub HYBRID              [15, ro, bool],
+            pub TSXLDTRK            [16, ro, bool],
+            pub PCONFIG             [18, ro, bool],
+            /// CET 间接分支跟踪，可通过 `CR4.CET` 使能。
+            pub CET_IBT             [20, ro, bool],
+            pub AMX_BF16            [22, ro, bool],
+            pub AVX512_FP16         [23, ro, bool],
+            pub AMX_TILE            [24, ro, bool],
+            pub AMX_INT8            [25, ro, bool],
+            pub IBRS_IBPB           [26, ro, bool],
+            pub STIBP               [27, ro, bool],
+            pub L1D_FLUSH           [28, ro, bool],
+            pub ARCH_CAPABILITIES   [29, ro, bool],
+            pub CORE_CAPABILITIES   [30, ro, bool],
+            pub SSBD                [31, ro, bool],
+        }
+        StructuredExtFeature [eax1] {
+            pub AVX_VNNI            [04, ro, bool],
+            pub AVX512_BF16         [05, ro, bool],
+            pub FZLRM               [10, ro, bool],
+            pub FSRS                [11, ro, bool],
+            pub FSRCS               [12, ro, bool],
+            pub HRESET              [22, ro, bool],
+            pub AVX_IFMA            [23, ro, bool],
+            pub LAM                 [26, ro, bool],
+        }
+        StructuredExtFeature [edx1] {
+            pub AVX_VNNI_INT8       [04, ro, bool],
+            pub AVX_NE_CONVERT      [05, ro, bool],
+            pub PREFETCHI           [14, ro, bool],
+            pub AVX10               [19, ro, bool],
+        }
+        StructuredExtFeature [edx2] {
+            pub PSFD                [00, ro, bool],
+            pub IPRED_CTRL          [01, ro, bool],
+            pub RRSBA_CTRL          [02, ro, bool],
+            pub DDPD_U              [03, ro, bool],
+            pub BHI_CTRL            [04, ro, bool],
+            pub MCDT_NO             [05, ro, bool],
+        }
+    }
+}
+
+/// # 扩展特性
+/// Extended Feature Flags
+///
+/// 由 `CPUID.(EAX=8000_0001h)` 的 ECX 和 EDX 组成，用于描述 long 模式、NX、SYSCALL/SYSRET 等扩展特性。
+///
+/// 若处理器不支持 8000_0001h 功能号，则所有特性位均为 0。
+#[derive(Debug, Default)]
+pub struct ExtFeature {
+    pub(crate) ecx: u32,
+    pub(crate) edx: u32,
+}
+
+impl_reg_buffer_trait!(ExtFeature);
+
+plain_field! {
+    ExtFeature {
+        pub support_lahf_lm:    ext_fields::LAHF_LM,
+        pub support_svm:        ext_fields::SVM,
+        pub support_tce:        ext_fields::TCE,
+        pub support_topology_ext: ext_fields::TOPOLOGY_EXT,
+        pub support_syscall:    ext_fields::SYSCALL,
+        pub support_nx:         ext_fields::NX,
+        pub support_ffxsr:      ext_fields::FFXSR,
+        pub support_page1gb:    ext_fields::PAGE1GB,
+        pub support_rdtscp:     ext_fields::RDTSCP,
+        pub support_long_mode:  ext_fields::LM,
+    }
+}
+
+pub mod ext_fields {
+    use super::ExtFeature;
+
+    bits::fields_ex! {
+        ExtFeature [ecx] {
+            /// 64 位模式下支持 LAHF/SAHF 指令
+            pub LAHF_LM             [00, ro, bool],
+            pub CMP_LEGACY          [01, ro, bool],
+            /// 安全虚拟机，可通过 `EFER.SVME` 使能。
+            pub SVM                 [02, ro, bool],
+            pub EXT_APIC_SPACE      [03, ro, bool],
+            pub ALT_MOV_CR8         [04, ro, bool],
+            /// LZCNT 指令
+            pub ABM                 [05, ro, bool],
+            pub SSE4A               [06, ro, bool],
+            pub MISALIGN_SSE        [07, ro, bool],
+            pub PREFETCHW           [08, ro, bool],
+            pub OSVW                [09, ro, bool],
+            pub IBS                 [10, ro, bool],
+            pub XOP                 [11, ro, bool],
+            pub SKINIT              [12, ro, bool],
+            pub WDT                 [13, ro, bool],
+            pub LWP                 [15, ro, bool],
+            pub FMA4                [16, ro, bool],
+            /// 转换缓存扩展，可通过 `EFER.TCE` 使能。
+            pub TCE                 [17, ro, bool],
+            pub NODE_ID             [19, ro, bool],
+            pub TBM                 [21, ro, bool],
+            pub TOPOLOGY_EXT        [22, ro, bool],
+            pub PERF_CTR_EXT_CORE   [23, ro, bool],
+            pub PERF_CTR_EXT_NB     [24, ro, bool],
+            pub DATA_BP_EXT         [26, ro, bool],
+            pub PERF_TSC            [27, ro, bool],
+            pub PERF_CTR_EXT_LLC    [28, ro, bool],
+            pub MONITORX            [29, ro, bool],
+            pub ADDR_MASK_EXT       [30, ro, bool],
+        }
+        ExtFeature [edx] {
+            /// SYSCALL/SYSRET 指令，可通过 `EFER.SCE` 使能。
+            pub SYSCALL             [11, ro, bool],
+            /// 非执行页保护，可通过 `EFER.NXE` 使能。
+            pub NX                  [20, ro, bool],
+            pub MMX_EXT             [22, ro, bool],
+            /// FXSAVE/FXRSTOR 优化，可通过 `EFER.FFXSR` 使能。
+            pub FFXSR               [25, ro, bool],
+            /// 1GB 大页
+            pub PAGE1GB             [26, ro, bool],
+            pub RDTSCP              [27, ro, bool],
+            /// long 模式
+            pub LM                  [29, ro, bool],
+            pub AMD_3DNOW_EXT       [30, ro, bool],
+            pub AMD_3DNOW           [31, ro, bool],
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::format;
+
+    use super::StdFeature;
+
+    #[test]
+    fn std_feature_display() {
+        let feature = StdFeature {
+            ebx: 0x0310_0800,
+            ecx: 0x7ffa_fbff,
+            edx: 0xbfeb_fbff,
+        };
+        assert_eq!(feature.initial_apic_id(), 3);
+        assert_eq!(feature.clflush_line_size(), 64);
+        assert_eq!(feature.max_logical_processors(), 16);
+        assert!(feature.support_apic() && feature.support_x2apic());
+        assert!(feature.flags().any(|flag| flag == "sse4_2"));
+        assert!(!feature.flags().any(|flag| flag == "hypervisor"));
+        assert!(feature.support_tm() && feature.support_tm2() && feature.support_pbe());
+        assert!(!feature.support_psn() && !feature.support_dca());
+        assert_eq!(feature.flags().count(), 56);
+
+        let text = format!("{}", feature);
+        assert!(text.starts_with("initial apicid\t: 3\nclflush size\t: 64\nsiblings\t: 16\n"));
+        assert!(text.contains("flags\t\t: fpu vme de pse tsc msr pae mce cx8 apic sep mtrr"));
+        assert!(text.ends_with("f16c rdrand\n"));
     }
 }
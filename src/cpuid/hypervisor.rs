@@ -0,0 +1,473 @@
+use core::fmt::Display;
+
+use super::{CpuidResult, CpuidSource};
+
+/// # 虚拟机监视器制造商
+///
+/// 由 `CPUID.(EAX=4000_0000h)` 返回的 12 字节签名（按 EBX、ECX、EDX 顺序拼接）决定。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HypervisorVendor {
+    /// `KVMKVMKVM\0\0\0`
+    Kvm,
+    /// `Microsoft Hv`，Hyper-V 或者实现了 Hyper-V 接口的其他虚拟机监视器
+    HyperV,
+    /// `VMwareVMware`
+    VMware,
+    /// `XenVMMXenVMM`
+    Xen,
+    /// `TCGTCGTCGTCG`，QEMU 的 TCG 模式（无硬件加速）
+    QemuTcg,
+    /// `bhyve bhyve `
+    Bhyve,
+    /// `ACRNACRNACRN`
+    Acrn,
+    /// `VBoxVBoxVBox`
+    VirtualBox,
+    /// ` lrpepyh  vr`
+    Parallels,
+    Unknown([u8; 12]),
+}
+
+impl HypervisorVendor {
+    pub fn from_bytes(bytes: [u8; 12]) -> Self {
+        match &bytes {
+            b"KVMKVMKVM\0\0\0" => HypervisorVendor::Kvm,
+            b"Microsoft Hv" => HypervisorVendor::HyperV,
+            b"VMwareVMware" => HypervisorVendor::VMware,
+            b"XenVMMXenVMM" => HypervisorVendor::Xen,
+            b"TCGTCGTCGTCG" => HypervisorVendor::QemuTcg,
+            b"bhyve bhyve " => HypervisorVendor::Bhyve,
+            b"ACRNACRNACRN" => HypervisorVendor::Acrn,
+            b"VBoxVBoxVBox" => HypervisorVendor::VirtualBox,
+            b" lrpepyh  vr" => HypervisorVendor::Parallels,
+            _ => HypervisorVendor::Unknown(bytes),
+        }
+    }
+    fn from_result(result: &CpuidResult) -> Self {
+        let mut bytes = [0u8; 12];
+        bytes[0..4].copy_from_slice(&result.ebx.to_le_bytes());
+        bytes[4..8].copy_from_slice(&result.ecx.to_le_bytes());
+        bytes[8..12].copy_from_slice(&result.edx.to_le_bytes());
+        Self::from_bytes(bytes)
+    }
+}
+
+impl Display for HypervisorVendor {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            HypervisorVendor::Kvm => f.write_str("KVM"),
+            HypervisorVendor::HyperV => f.write_str("Microsoft Hv"),
+            HypervisorVendor::VMware => f.write_str("VMware"),
+            HypervisorVendor::Xen => f.write_str("Xen"),
+            HypervisorVendor::QemuTcg => f.write_str("QEMU TCG"),
+            HypervisorVendor::Bhyve => f.write_str("bhyve"),
+            HypervisorVendor::Acrn => f.write_str("ACRN"),
+            HypervisorVendor::VirtualBox => f.write_str("VirtualBox"),
+            HypervisorVendor::Parallels => f.write_str("Parallels"),
+            HypervisorVendor::Unknown(bytes) => {
+                f.write_str(core::str::from_utf8(bytes).unwrap_or("unknown hypervisor"))
+            }
+        }
+    }
+}
+
+/// # 虚拟机监视器
+///
+/// 只有在 `CPUID.(EAX=01h):ECX[31] = 1` 时，4000_0000h 开始的功能号才有意义。
+///
+/// 部分虚拟机监视器会同时提供多套接口，例如 KVM 在使能 Hyper-V 兼容接口后，
+/// 4000_0000h 处为 Hyper-V 的签名，KVM 自身的签名则移到 4000_0100h 处。
+/// 因此这里以 0x100 为步长，在 4000_0000h..=4001_0000h 范围内查找。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hypervisor {
+    base: u32,
+    vendor: HypervisorVendor,
+    max_leaf: u32,
+}
+
+impl Hypervisor {
+    const FIRST_BASE: u32 = 0x4000_0000;
+    const LAST_BASE: u32 = 0x4001_0000;
+
+    fn at<S: CpuidSource + ?Sized>(cpuid: &S, base: u32) -> Option<Self> {
+        let result = cpuid.query(base as usize, 0);
+        // 签名全为 0 或最大功能号小于基址时，该位置没有虚拟机监视器
+        if result.ebx == 0 && result.ecx == 0 && result.edx == 0 {
+            return None;
+        }
+        // KVM 早期版本的最大功能号为 0，此时视为 base + 1
+        let max_leaf = if result.eax == 0 {
+            base + 1
+        } else if result.eax < base {
+            return None;
+        } else {
+            result.eax
+        };
+        Some(Self {
+            base,
+            vendor: HypervisorVendor::from_result(&result),
+            max_leaf,
+        })
+    }
+
+    /// 返回第一个（即 4000_0000h 处）虚拟机监视器
+    pub(crate) fn detect<S: CpuidSource + ?Sized>(cpuid: &S) -> Option<Self> {
+        Self::detect_at(cpuid, Self::FIRST_BASE)
+    }
+
+    /// 返回起始功能号为 `base` 的虚拟机监视器接口
+    pub(crate) fn detect_at<S: CpuidSource + ?Sized>(cpuid: &S, base: u32) -> Option<Self> {
+        if !cpuid.std_feature().hypervisor_present() {
+            return None;
+        }
+        Self::at(cpuid, base)
+    }
+
+    /// 查找指定制造商的虚拟机监视器接口
+    pub(crate) fn find<S: CpuidSource + ?Sized>(
+        cpuid: &S,
+        vendor: HypervisorVendor,
+    ) -> Option<Self> {
+        Self::detect_all(cpuid).find(|hypervisor| hypervisor.vendor == vendor)
+    }
+
+    /// 按起始功能号依次返回所有虚拟机监视器接口
+    pub(crate) fn detect_all<S: CpuidSource + ?Sized>(
+        cpuid: &S,
+    ) -> impl Iterator<Item = Self> + '_ {
+        let present = cpuid.std_feature().hypervisor_present();
+        (Self::FIRST_BASE..=Self::LAST_BASE)
+            .step_by(0x100)
+            .filter(move |_| present)
+            .filter_map(move |base| Self::at(cpuid, base))
+    }
+
+    pub fn vendor(&self) -> HypervisorVendor {
+        self.vendor
+    }
+    /// 该虚拟机监视器接口的起始功能号
+    pub fn base(&self) -> u32 {
+        self.base
+    }
+    /// 该虚拟机监视器接口所支持的最大功能号
+    pub fn max_leaf(&self) -> u32 {
+        self.max_leaf
+    }
+    /// 查询相对于起始功能号偏移为 `offset` 的功能号，超出最大功能号时返回 None。
+    pub fn query<S: CpuidSource + ?Sized>(&self, cpuid: &S, offset: u32) -> Option<CpuidResult> {
+        let leaf = self.base.checked_add(offset)?;
+        if leaf > self.max_leaf {
+            return None;
+        }
+        Some(cpuid.query(leaf as usize, 0))
+    }
+}
+
+/// # KVM 半虚拟化特性
+///
+/// 由 `CPUID.(EAX=4000_0001h)` 的 EAX（特性位）和 EDX（提示位）组成，
+/// 相对于 KVM 接口的起始功能号。
+#[derive(Debug, Default)]
+pub struct KvmFeature {
+    pub(crate) eax: u32,
+    pub(crate) edx: u32,
+}
+
+impl KvmFeature {
+    pub(crate) fn from_hypervisor<S: CpuidSource + ?Sized>(
+        cpuid: &S,
+        hypervisor: &Hypervisor,
+    ) -> Option<Self> {
+        let result = hypervisor.query(cpuid, 1)?;
+        Some(Self {
+            eax: result.eax,
+            edx: result.edx,
+        })
+    }
+}
+
+impl_reg_buffer_trait!(KvmFeature);
+
+plain_field! {
+    KvmFeature {
+        pub support_clocksource:    kvm_fields::CLOCKSOURCE,
+        pub support_clocksource2:   kvm_fields::CLOCKSOURCE2,
+        pub support_steal_time:     kvm_fields::STEAL_TIME,
+        pub support_pv_eoi:         kvm_fields::PV_EOI,
+        pub support_pv_unhalt:      kvm_fields::PV_UNHALT,
+        pub support_pv_tlb_flush:   kvm_fields::PV_TLB_FLUSH,
+        pub support_pv_send_ipi:    kvm_fields::PV_SEND_IPI,
+        pub clocksource_stable:     kvm_fields::CLOCKSOURCE_STABLE_BIT,
+        pub realtime_hint:          kvm_fields::REALTIME,
+    }
+}
+
+pub mod kvm_fields {
+    use super::KvmFeature;
+
+    bits::fields_ex! {
+        KvmFeature [eax] {
+            /// kvmclock，使用 MSR 11h/12h（已废弃）
+            pub CLOCKSOURCE             [00, ro, bool],
+            pub NOP_IO_DELAY            [01, ro, bool],
+            pub MMU_OP                  [02, ro, bool],
+            /// kvmclock，使用 MSR 4B564D00h/4B564D01h
+            pub CLOCKSOURCE2            [03, ro, bool],
+            pub ASYNC_PF                [04, ro, bool],
+            pub STEAL_TIME              [05, ro, bool],
+            pub PV_EOI                  [06, ro, bool],
+            pub PV_UNHALT               [07, ro, bool],
+            pub PV_TLB_FLUSH            [09, ro, bool],
+            pub ASYNC_PF_VMEXIT         [10, ro, bool],
+            pub PV_SEND_IPI             [11, ro, bool],
+            pub POLL_CONTROL            [12, ro, bool],
+            pub PV_SCHED_YIELD          [13, ro, bool],
+            pub ASYNC_PF_INT            [14, ro, bool],
+            pub MSI_EXT_DEST_ID         [15, ro, bool],
+            pub HC_MAP_GPA_RANGE        [16, ro, bool],
+            pub MIGRATION_CONTROL       [17, ro, bool],
+            /// kvmclock 的 PVCLOCK_TSC_STABLE_BIT 有效
+            pub CLOCKSOURCE_STABLE_BIT  [24, ro, bool],
+        }
+        KvmFeature [edx] {
+            /// vCPU 不会被抢占，可以不使用 PV spinlock
+            pub REALTIME                [00, ro, bool],
+        }
+    }
+}
+
+/// # Hyper-V 分区特权与特性
+///
+/// 由 `CPUID.(EAX=4000_0003h)` 组成，相对于 Hyper-V 接口的起始功能号：
+///
+/// + EAX：分区特权掩码的低 32 位，主要控制合成 MSR 的访问权限；
+/// + EBX：分区特权掩码的高 32 位，主要控制超级调用的使用权限；
+/// + EDX：其他特性。
+///
+/// 只有 `CPUID.(EAX=4000_0001h):EAX` 为 `Hv#1` 时才会构建本结构体。
+#[derive(Debug, Default)]
+pub struct HyperVFeature {
+    pub(crate) eax: u32,
+    pub(crate) ebx: u32,
+    pub(crate) edx: u32,
+}
+
+impl HyperVFeature {
+    /// `Hv#1`
+    const INTERFACE_SIGNATURE: u32 = 0x3123_7648;
+
+    pub(crate) fn from_hypervisor<S: CpuidSource + ?Sized>(
+        cpuid: &S,
+        hypervisor: &Hypervisor,
+    ) -> Option<Self> {
+        if hypervisor.query(cpuid, 1)?.eax != Self::INTERFACE_SIGNATURE {
+            return None;
+        }
+        let result = hypervisor.query(cpuid, 3)?;
+        Some(Self {
+            eax: result.eax,
+            ebx: result.ebx,
+            edx: result.edx,
+        })
+    }
+    /// 64 位的分区特权掩码
+    pub fn privilege_mask(&self) -> u64 {
+        (self.eax as u64) | ((self.ebx as u64) << 32)
+    }
+}
+
+impl_reg_buffer_trait!(HyperVFeature);
+
+plain_field! {
+    HyperVFeature {
+        pub access_vp_runtime:          hyperv_fields::ACCESS_VP_RUNTIME_REG,
+        pub access_reference_counter:   hyperv_fields::ACCESS_PARTITION_REFERENCE_COUNTER,
+        pub access_synic:               hyperv_fields::ACCESS_SYNIC_REGS,
+        pub access_synthetic_timer:     hyperv_fields::ACCESS_SYNTHETIC_TIMER_REGS,
+        pub access_apic_msrs:           hyperv_fields::ACCESS_INTR_CTRL_REGS,
+        pub access_hypercall_msrs:      hyperv_fields::ACCESS_HYPERCALL_MSRS,
+        pub access_vp_index:            hyperv_fields::ACCESS_VP_INDEX,
+        pub access_reference_tsc:       hyperv_fields::ACCESS_PARTITION_REFERENCE_TSC,
+        pub access_frequency_msrs:      hyperv_fields::ACCESS_FREQUENCY_REGS,
+    }
+}
+
+pub mod hyperv_fields {
+    use super::HyperVFeature;
+
+    bits::fields_ex! {
+        HyperVFeature [eax] {
+            /// HV_X64_MSR_VP_RUNTIME
+            pub ACCESS_VP_RUNTIME_REG               [00, ro, bool],
+            /// HV_X64_MSR_TIME_REF_COUNT
+            pub ACCESS_PARTITION_REFERENCE_COUNTER  [01, ro, bool],
+            /// SynIC 相关 MSR
+            pub ACCESS_SYNIC_REGS                   [02, ro, bool],
+            /// 合成定时器相关 MSR
+            pub ACCESS_SYNTHETIC_TIMER_REGS         [03, ro, bool],
+            /// HV_X64_MSR_EOI、HV_X64_MSR_ICR、HV_X64_MSR_TPR
+            pub ACCESS_INTR_CTRL_REGS               [04, ro, bool],
+            /// HV_X64_MSR_GUEST_OS_ID、HV_X64_MSR_HYPERCALL
+            pub ACCESS_HYPERCALL_MSRS               [05, ro, bool],
+            /// HV_X64_MSR_VP_INDEX
+            pub ACCESS_VP_INDEX                     [06, ro, bool],
+            pub ACCESS_RESET_REG                    [07, ro, bool],
+            pub ACCESS_STATS_REG                    [08, ro, bool],
+            /// HV_X64_MSR_REFERENCE_TSC
+            pub ACCESS_PARTITION_REFERENCE_TSC      [09, ro, bool],
+            pub ACCESS_GUEST_IDLE_REG               [10, ro, bool],
+            /// HV_X64_MSR_TSC_FREQUENCY、HV_X64_MSR_APIC_FREQUENCY
+            pub ACCESS_FREQUENCY_REGS               [11, ro, bool],
+            pub ACCESS_DEBUG_REGS                   [12, ro, bool],
+            pub ACCESS_REENLIGHTENMENT_CONTROLS     [13, ro, bool],
+        }
+        HyperVFeature [ebx] {
+            pub CREATE_PARTITIONS                   [00, ro, bool],
+            pub ACCESS_PARTITION_ID                 [01, ro, bool],
+            pub ACCESS_MEMORY_POOL                  [02, ro, bool],
+            pub POST_MESSAGES                       [04, ro, bool],
+            pub SIGNAL_EVENTS                       [05, ro, bool],
+            pub CREATE_PORT                         [06, ro, bool],
+            pub CONNECT_PORT                        [07, ro, bool],
+            pub ACCESS_STATS                        [08, ro, bool],
+            pub DEBUGGING                           [11, ro, bool],
+            pub CPU_MANAGEMENT                      [12, ro, bool],
+            pub ACCESS_VSM                          [16, ro, bool],
+            pub ACCESS_VP_REGISTERS                 [17, ro, bool],
+            pub ENABLE_EXTENDED_HYPERCALLS          [20, ro, bool],
+            pub START_VIRTUAL_PROCESSOR             [21, ro, bool],
+        }
+        HyperVFeature [edx] {
+            pub GUEST_DEBUGGING                     [01, ro, bool],
+            pub PERFORMANCE_MONITOR                 [02, ro, bool],
+            pub XMM_FAST_HYPERCALL_INPUT            [04, ro, bool],
+            pub GUEST_IDLE                          [05, ro, bool],
+            /// 可以通过 HV_X64_MSR_TSC_FREQUENCY、HV_X64_MSR_APIC_FREQUENCY 查询频率
+            pub FREQUENCY_MSRS_AVAILABLE            [08, ro, bool],
+            pub SYNTHETIC_MACHINE_CHECK             [09, ro, bool],
+            pub GUEST_CRASH_MSRS                    [10, ro, bool],
+            pub DEBUG_MSRS                          [11, ro, bool],
+            pub FAST_HYPERCALL_OUTPUT               [15, ro, bool],
+            pub DIRECT_SYNTHETIC_TIMERS             [19, ro, bool],
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::println;
+
+    use crate::cpuid::{table::CpuidTable, Cpuid, CpuidResult, CpuidSource};
+
+    use super::HypervisorVendor;
+
+    fn signature(bytes: &[u8; 12], max_leaf: u32) -> CpuidResult {
+        let reg =
+            |i: usize| u32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]);
+        CpuidResult {
+            eax: max_leaf,
+            ebx: reg(0),
+            ecx: reg(4),
+            edx: reg(8),
+        }
+    }
+
+    #[test]
+    fn hypervisor_test() {
+        if let Some(cpuid) = Cpuid::inst() {
+            if let Some(hypervisor) = cpuid.hypervisor() {
+                println!("{} {:#x}", hypervisor.vendor(), hypervisor.max_leaf());
+            }
+        }
+    }
+
+    #[test]
+    fn kvm_with_hyperv_enlightenment() {
+        let mut table = CpuidTable::new();
+        table
+            .insert(
+                0x00,
+                0,
+                CpuidResult {
+                    eax: 0x01,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        table
+            .insert(
+                0x01,
+                0,
+                CpuidResult {
+                    ecx: 1 << 31,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        table
+            .insert(0x4000_0000, 0, signature(b"Microsoft Hv", 0x4000_000a))
+            .unwrap();
+        table
+            .insert(
+                0x4000_0001,
+                0,
+                CpuidResult {
+                    eax: 0x3123_7648,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        table
+            .insert(
+                0x4000_0003,
+                0,
+                CpuidResult {
+                    eax: 0x0000_0a7f,
+                    ebx: 0x0000_0030,
+                    ecx: 0,
+                    edx: 0x0000_0100,
+                },
+            )
+            .unwrap();
+        table
+            .insert(0x4000_0100, 0, signature(b"KVMKVMKVM\0\0\0", 0x4000_0101))
+            .unwrap();
+        table
+            .insert(
+                0x4000_0101,
+                0,
+                CpuidResult {
+                    eax: 0x0100_0ef9,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        let hypervisor = table.hypervisor().unwrap();
+        assert_eq!(hypervisor.vendor(), HypervisorVendor::HyperV);
+        assert!(hypervisor.query(&table, 0x0b).is_none());
+        assert!(hypervisor.query(&table, u32::MAX).is_none());
+
+        let hyperv = table.hyperv_feature().unwrap();
+        assert!(hyperv.access_reference_tsc());
+        assert!(hyperv.access_frequency_msrs());
+        assert_eq!(hyperv.privilege_mask(), 0x0000_0030_0000_0a7f);
+
+        let kvm = table.kvm_feature().unwrap();
+        assert!(kvm.support_clocksource2());
+        assert!(kvm.support_pv_eoi());
+        assert!(kvm.clocksource_stable());
+        assert!(!kvm.realtime_hint());
+    }
+
+    #[test]
+    fn bare_metal() {
+        let mut table = CpuidTable::new();
+        table
+            .insert(0x4000_0000, 0, signature(b"KVMKVMKVM\0\0\0", 0x4000_0001))
+            .unwrap();
+        // 未设置 hypervisor-present bit
+        assert!(table.hypervisor().is_none());
+        assert!(table.kvm_feature().is_none());
+    }
+}
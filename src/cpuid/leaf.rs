@@ -0,0 +1,248 @@
+use core::fmt::Display;
+
+use super::{hypervisor::Hypervisor, CpuidResult, CpuidSource};
+
+/// 查询不受支持的功能号或子功能号时产生的错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CpuidError {
+    /// 功能号超出其所在范围的最大功能号；该范围不存在时 `max_leaf` 为 0。
+    LeafNotSupported { leaf: u32, max_leaf: u32 },
+    /// 功能号受支持，但子功能号无效
+    SubLeafNotSupported { leaf: u32, sub_leaf: u32 },
+}
+
+impl Display for CpuidError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            CpuidError::LeafNotSupported { leaf, max_leaf } => write!(
+                f,
+                "CPUID leaf {:#x} is not supported (max leaf {:#x})",
+                leaf, max_leaf
+            ),
+            CpuidError::SubLeafNotSupported { leaf, sub_leaf } => write!(
+                f,
+                "CPUID leaf {:#x} sub-leaf {:#x} is not supported",
+                leaf, sub_leaf
+            ),
+        }
+    }
+}
+
+/// 虚拟机监视器功能号的范围
+const HYPERVISOR_LEAVES: core::ops::Range<u32> = 0x4000_0000..0x5000_0000;
+
+/// # 检查功能号和子功能号
+///
+/// 功能号按高 16 位分为多个范围，每个范围的起始功能号返回该范围的最大功能号：
+///
+/// + `0000_0000h`：基本功能号；
+/// + `4000_0000h`：虚拟机监视器功能号，以 0x100 为步长可能存在多套接口，
+///   只有在 `CPUID.(EAX=01h):ECX[31] = 1` 时才有意义；
+/// + `8000_0000h`：扩展功能号；其他范围（如 Centaur 的 `C000_0000h`）与扩展功能号的规则一致，
+///   即最大功能号必须落在该范围之内。
+///
+/// Intel 处理器在功能号超出最大基本功能号时，会返回最大基本功能号的数据，而不是全 0，
+/// 因此必须先检查功能号。
+///
+/// 对于带有子功能号的功能号，按各自的枚举方式检查子功能号：
+///
+/// + 07h、14h、17h、18h、1Dh、20h：子功能号 0 的 EAX 为最大子功能号；
+/// + 04h、8000_001Dh：缓存类型为 0 的子功能号无效；
+/// + 0Bh、1Fh：层级类型为 0 的子功能号无效；
+/// + 0Dh：子功能号 2..=63 必须是 XCR0 或 IA32_XSS 所支持的状态组件；
+/// + 0Fh、10h：子功能号必须是子功能号 0 中枚举的资源类型。
+///
+/// 其他功能号不使用 ECX，任意子功能号均返回相同的结果。
+pub(crate) fn query_checked<S: CpuidSource + ?Sized>(
+    cpuid: &S,
+    leaf: u32,
+    sub_leaf: u32,
+) -> Result<CpuidResult, CpuidError> {
+    check_leaf(cpuid, leaf)?;
+    let result = cpuid.query(leaf as usize, sub_leaf as usize);
+    if is_sub_leaf_valid(cpuid, leaf, sub_leaf, &result) {
+        Ok(result)
+    } else {
+        Err(CpuidError::SubLeafNotSupported { leaf, sub_leaf })
+    }
+}
+
+/// 所支持的最大基本功能号
+pub(crate) fn max_basic_leaf<S: CpuidSource + ?Sized>(cpuid: &S) -> u32 {
+    cpuid.query(0x00, 0).eax
+}
+
+/// `base` 所在范围的最大功能号，若该范围不存在，则返回 0。
+pub(crate) fn max_leaf_in_range<S: CpuidSource + ?Sized>(cpuid: &S, base: u32) -> u32 {
+    let max_leaf = cpuid.query(base as usize, 0).eax;
+    if max_leaf & 0xffff_0000 == base {
+        max_leaf
+    } else {
+        0
+    }
+}
+
+fn check_leaf<S: CpuidSource + ?Sized>(cpuid: &S, leaf: u32) -> Result<(), CpuidError> {
+    let max_leaf = if leaf < HYPERVISOR_LEAVES.start {
+        if leaf == 0 {
+            return Ok(());
+        }
+        max_basic_leaf(cpuid)
+    } else if HYPERVISOR_LEAVES.contains(&leaf) {
+        Hypervisor::detect_at(cpuid, leaf & !0xff)
+            .map(|hypervisor| hypervisor.max_leaf())
+            .unwrap_or(0)
+    } else {
+        max_leaf_in_range(cpuid, leaf & 0xffff_0000)
+    };
+    if max_leaf != 0 && leaf <= max_leaf {
+        Ok(())
+    } else {
+        Err(CpuidError::LeafNotSupported { leaf, max_leaf })
+    }
+}
+
+fn is_sub_leaf_valid<S: CpuidSource + ?Sized>(
+    cpuid: &S,
+    leaf: u32,
+    sub_leaf: u32,
+    result: &CpuidResult,
+) -> bool {
+    match leaf {
+        0x07 | 0x14 | 0x17 | 0x18 | 0x1d | 0x20 => {
+            sub_leaf == 0 || sub_leaf <= cpuid.query(leaf as usize, 0).eax
+        }
+        0x04 | 0x8000_001d => result.eax & 0x1f != 0,
+        0x0b | 0x1f => (result.ecx >> 8) & 0xff != 0,
+        0x0d => {
+            if sub_leaf < 2 {
+                return true;
+            }
+            if sub_leaf >= 64 {
+                return false;
+            }
+            let sub0 = cpuid.query(0x0d, 0);
+            let sub1 = cpuid.query(0x0d, 1);
+            let supported = (sub0.eax as u64)
+                | ((sub0.edx as u64) << 32)
+                | (sub1.ecx as u64)
+                | ((sub1.edx as u64) << 32);
+            supported & (1 << sub_leaf) != 0
+        }
+        // 0Fh：EDX 中枚举可监控的资源；10h：EBX 中枚举可分配的资源
+        0x0f | 0x10 => {
+            if sub_leaf == 0 {
+                return true;
+            }
+            if sub_leaf >= 32 {
+                return false;
+            }
+            let sub0 = cpuid.query(leaf as usize, 0);
+            let resources = if leaf == 0x0f { sub0.edx } else { sub0.ebx };
+            resources & (1 << sub_leaf) != 0
+        }
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cpuid::{table::CpuidTable, CpuidResult, CpuidSource};
+
+    use super::CpuidError;
+
+    #[test]
+    fn leaf_ranges() {
+        let table = CpuidTable::parse(include_str!("testdata/coffee_lake.txt")).unwrap();
+        assert_eq!(table.max_basic_leaf(), 0x16);
+        assert_eq!(table.max_ext_leaf(), 0x8000_0008);
+        assert_eq!(table.max_hypervisor_leaf(), None);
+
+        assert!(table.query_checked(0x16, 0).is_ok());
+        assert_eq!(
+            table.query_checked(0x17, 0).unwrap_err(),
+            CpuidError::LeafNotSupported {
+                leaf: 0x17,
+                max_leaf: 0x16
+            }
+        );
+        assert!(table.query_checked(0x8000_0008, 0).is_ok());
+        assert!(table.query_checked(0x8000_0009, 0).is_err());
+        assert_eq!(
+            table.query_checked(0x4000_0000, 0).unwrap_err(),
+            CpuidError::LeafNotSupported {
+                leaf: 0x4000_0000,
+                max_leaf: 0
+            }
+        );
+        assert!(table.query_checked(0xc000_0000, 0).is_err());
+    }
+
+    #[test]
+    fn sub_leaves() {
+        let table = CpuidTable::parse(include_str!("testdata/coffee_lake.txt")).unwrap();
+        // 07h 只有子功能号 0
+        assert!(table.query_checked(0x07, 0).is_ok());
+        assert_eq!(
+            table.query_checked(0x07, 1).unwrap_err(),
+            CpuidError::SubLeafNotSupported {
+                leaf: 0x07,
+                sub_leaf: 1
+            }
+        );
+        // L1d、L1i、L2、L3
+        assert!(table.query_checked(0x04, 3).is_ok());
+        assert!(table.query_checked(0x04, 4).is_err());
+        // SMT、Core
+        assert!(table.query_checked(0x0b, 1).is_ok());
+        assert!(table.query_checked(0x0b, 2).is_err());
+        // XCR0 = x87 | SSE | AVX | BNDREGS | BNDCSR
+        assert!(table.query_checked(0x0d, 4).is_ok());
+        assert!(table.query_checked(0x0d, 5).is_err());
+        // 不使用 ECX 的功能号
+        assert!(table.query_checked(0x01, 7).is_ok());
+    }
+
+    #[test]
+    fn hypervisor_leaves() {
+        let mut table = CpuidTable::new();
+        table
+            .insert(
+                0x00,
+                0,
+                CpuidResult {
+                    eax: 0x01,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        table
+            .insert(
+                0x01,
+                0,
+                CpuidResult {
+                    ecx: 1 << 31,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        table
+            .insert(
+                0x4000_0000,
+                0,
+                CpuidResult {
+                    eax: 0x4000_0001,
+                    ebx: 0x4b4d_564b,
+                    ecx: 0x564b_4d56,
+                    edx: 0x4d,
+                },
+            )
+            .unwrap();
+        assert_eq!(table.max_hypervisor_leaf(), Some(0x4000_0001));
+        assert!(table.query_checked(0x4000_0001, 0).is_ok());
+        assert!(table.query_checked(0x4000_0002, 0).is_err());
+        assert!(table.query_checked(0x4000_0101, 0).is_err());
+        // 最大基本功能号为 1
+        assert!(table.query_checked(0x02, 0).is_err());
+    }
+}
@@ -0,0 +1,289 @@
+use register::RegisterBufferReader;
+
+use super::{feature::ext_fields, CpuidResult, CpuidSource};
+
+/// 架构性能监控事件，即 `CPUID.(EAX=0Ah):EBX` 中各 bit 对应的事件。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchEvent {
+    CoreCycles = 0,
+    InstructionsRetired = 1,
+    ReferenceCycles = 2,
+    LlcReferences = 3,
+    LlcMisses = 4,
+    BranchInstructionsRetired = 5,
+    BranchMissesRetired = 6,
+    TopdownSlots = 7,
+}
+
+/// # AMD PerfMonV2 信息
+///
+/// 由 `CPUID.(EAX=8000_0022h)` 的 EAX（特性位）和 EBX（计数器数量）组成。
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AmdPerfMonV2 {
+    pub(crate) eax: u32,
+    pub(crate) ebx: u32,
+}
+
+impl_reg_buffer_trait!(AmdPerfMonV2);
+
+plain_field! {
+    AmdPerfMonV2 {
+        /// 支持 PerfCntrGlobalCtl、PerfCntrGlobalStatus 等全局控制寄存器
+        pub support_perfmon_v2:     amd_fields::PerfMonV2,
+        pub support_lbr_stack:      amd_fields::LbrStack,
+        pub support_lbr_pmc_freeze: amd_fields::LbrAndPmcFreeze,
+        /// 每个 core 的性能计数器数量
+        pub core_counters:          amd_fields::NumPerfCtrCore,
+        pub lbr_stack_size:         amd_fields::LbrV2StackSize,
+        /// Data Fabric（北桥）性能计数器数量
+        pub df_counters:            amd_fields::NumPerfCtrNb,
+        /// 内存控制器（UMC）性能计数器数量
+        pub umc_counters:           amd_fields::NumPerfCtrUmc,
+    }
+}
+
+pub mod amd_fields {
+    use super::AmdPerfMonV2;
+
+    bits::fields_ex! {
+        AmdPerfMonV2 [eax] {
+            pub PerfMonV2           [00, ro, bool],
+            pub LbrStack            [01, ro, bool],
+            pub LbrAndPmcFreeze     [02, ro, bool],
+        }
+        AmdPerfMonV2 [ebx] {
+            pub NumPerfCtrCore      [00..=03, ro, u8],
+            pub LbrV2StackSize      [04..=09, ro, u8],
+            pub NumPerfCtrNb        [10..=15, ro, u8],
+            pub NumPerfCtrUmc       [16..=21, ro, u8],
+        }
+    }
+}
+
+/// # 性能监控信息
+///
+/// Intel 处理器由 `CPUID.(EAX=0Ah)` 解码而来：
+///
+/// + `EAX[7:0]`：架构性能监控版本，为 0 时不支持架构性能监控；
+/// + `EAX[15:8]`、`EAX[23:16]`：通用计数器的数量和位宽；
+/// + `EAX[31:24]`：EBX 中有效的事件数量；
+/// + EBX：bit 为 1 时对应的 [`ArchEvent`] 不可用；
+/// + ECX：版本 5 起枚举可用的固定功能计数器；
+/// + `EDX[4:0]`、`EDX[12:5]`：固定功能计数器的数量和位宽（版本 2 起有效）。
+///
+/// AMD 处理器没有架构性能监控，这里按 Linux 的做法处理：
+/// 支持 PerfMonV2（8000_0022h）时版本视为 2，计数器数量由 8000_0022h 给出；
+/// 否则版本为 0，根据 `CPUID.(EAX=8000_0001h):ECX[23]` 确定有 4 个或 6 个计数器。
+/// AMD 的计数器位宽均为 48 bit，且没有固定功能计数器。
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PerfMonInfo {
+    version: u8,
+    gp_counters: u8,
+    gp_width: u8,
+    fixed_counters: u8,
+    fixed_width: u8,
+    fixed_mask: u32,
+    event_count: u8,
+    unavailable_events: u32,
+    amd_v2: Option<AmdPerfMonV2>,
+}
+
+impl PerfMonInfo {
+    const AMD_COUNTER_WIDTH: u8 = 48;
+
+    pub(crate) fn from_cpuid<S: CpuidSource + ?Sized>(cpuid: &S) -> Option<Self> {
+        if cpuid.vendor().is_amd_compatible() {
+            return Some(Self::from_amd(cpuid));
+        }
+        let result = cpuid.query_checked(0x0a, 0).ok()?;
+        let info = Self::from_intel(&result);
+        if info.version == 0 {
+            None
+        } else {
+            Some(info)
+        }
+    }
+
+    pub(crate) fn from_intel(result: &CpuidResult) -> Self {
+        let version = result.eax as u8;
+        let (fixed_counters, fixed_width) = if version > 1 {
+            ((result.edx & 0x1f) as u8, (result.edx >> 5) as u8)
+        } else {
+            (0, 0)
+        };
+        Self {
+            version,
+            gp_counters: (result.eax >> 8) as u8,
+            gp_width: (result.eax >> 16) as u8,
+            fixed_counters,
+            fixed_width,
+            fixed_mask: if version >= 5 { result.ecx } else { 0 },
+            event_count: (result.eax >> 24) as u8,
+            unavailable_events: result.ebx,
+            amd_v2: None,
+        }
+    }
+
+    fn from_amd<S: CpuidSource + ?Sized>(cpuid: &S) -> Self {
+        let amd_v2 = cpuid
+            .query_checked(0x8000_0022, 0)
+            .ok()
+            .map(|result| AmdPerfMonV2 {
+                eax: result.eax,
+                ebx: result.ebx,
+            })
+            .filter(|v2| v2.support_perfmon_v2());
+        let (version, gp_counters) = match amd_v2 {
+            Some(v2) => (2, v2.core_counters()),
+            None if cpuid.ext_feature().read::<ext_fields::PERF_CTR_EXT_CORE>() => (0, 6),
+            None => (0, 4),
+        };
+        Self {
+            version,
+            gp_counters,
+            gp_width: Self::AMD_COUNTER_WIDTH,
+            amd_v2,
+            ..Self::default()
+        }
+    }
+
+    /// 架构性能监控版本
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+    /// 每个逻辑处理器的通用计数器数量
+    pub fn gp_counters(&self) -> u8 {
+        self.gp_counters
+    }
+    /// 通用计数器位宽
+    pub fn gp_width(&self) -> u8 {
+        self.gp_width
+    }
+    /// 固定功能计数器数量
+    pub fn fixed_counters(&self) -> u8 {
+        self.fixed_counters
+    }
+    /// 固定功能计数器位宽
+    pub fn fixed_width(&self) -> u8 {
+        self.fixed_width
+    }
+    /// 第 `index` 个固定功能计数器是否可用，即 `ECX[index] || EDX[4:0] > index`。
+    pub fn is_fixed_counter_supported(&self, index: u8) -> bool {
+        index < self.fixed_counters || (index < 32 && self.fixed_mask & (1 << index) != 0)
+    }
+    /// 架构事件是否可用；超出 `EAX[31:24]` 范围的事件均不可用。
+    pub fn is_event_available(&self, event: ArchEvent) -> bool {
+        let index = event as u8;
+        index < self.event_count && self.unavailable_events & (1 << index) == 0
+    }
+    /// 通用计数器中有效 bit 的掩码
+    pub fn gp_counter_mask(&self) -> u64 {
+        match self.gp_width {
+            0 => 0,
+            64..=u8::MAX => u64::MAX,
+            width => (1 << width) - 1,
+        }
+    }
+    /// AMD PerfMonV2 信息
+    pub fn amd_v2(&self) -> Option<&AmdPerfMonV2> {
+        self.amd_v2.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cpuid::{table::CpuidTable, CpuidResult, CpuidSource};
+
+    use super::{ArchEvent, PerfMonInfo};
+
+    #[test]
+    fn intel_arch_perfmon() {
+        let table = CpuidTable::parse(include_str!("testdata/coffee_lake.txt")).unwrap();
+        let info = table.perfmon_info().unwrap();
+        assert_eq!(info.version(), 4);
+        assert_eq!(info.gp_counters(), 4);
+        assert_eq!(info.gp_width(), 48);
+        assert_eq!(info.gp_counter_mask(), 0xffff_ffff_ffff);
+        assert_eq!(info.fixed_counters(), 3);
+        assert_eq!(info.fixed_width(), 48);
+        assert!(info.is_fixed_counter_supported(2));
+        assert!(!info.is_fixed_counter_supported(3));
+        assert!(info.is_event_available(ArchEvent::BranchMissesRetired));
+        assert!(!info.is_event_available(ArchEvent::TopdownSlots));
+        assert!(info.amd_v2().is_none());
+
+        // 版本 5：ECX 枚举固定功能计数器，EBX 中 LLC misses 不可用
+        let info = PerfMonInfo::from_intel(&CpuidResult {
+            eax: 0x0830_0805,
+            ebx: 0x0000_0010,
+            ecx: 0x0000_0009,
+            edx: 0x0000_8603,
+        });
+        assert!(info.is_fixed_counter_supported(3));
+        assert!(!info.is_fixed_counter_supported(4));
+        assert!(!info.is_event_available(ArchEvent::LlcMisses));
+        assert!(info.is_event_available(ArchEvent::TopdownSlots));
+    }
+
+    #[test]
+    fn amd_perfmon_v2() {
+        let mut table = CpuidTable::new();
+        // AuthenticAMD
+        table
+            .insert(
+                0x00,
+                0,
+                CpuidResult {
+                    eax: 0x10,
+                    ebx: 0x6874_7541,
+                    ecx: 0x444d_4163,
+                    edx: 0x6974_6e65,
+                },
+            )
+            .unwrap();
+        table
+            .insert(
+                0x8000_0000,
+                0,
+                CpuidResult {
+                    eax: 0x8000_0022,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        table
+            .insert(
+                0x8000_0001,
+                0,
+                CpuidResult {
+                    ecx: 1 << 23,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let info = table.perfmon_info().unwrap();
+        assert_eq!((info.version(), info.gp_counters()), (0, 6));
+
+        table
+            .insert(
+                0x8000_0022,
+                0,
+                CpuidResult {
+                    eax: 0x0000_0007,
+                    ebx: 0x0001_1106,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let info = table.perfmon_info().unwrap();
+        assert_eq!(info.version(), 2);
+        assert_eq!(info.gp_counters(), 6);
+        assert_eq!(info.gp_width(), 48);
+        assert_eq!(info.fixed_counters(), 0);
+        let v2 = info.amd_v2().unwrap();
+        assert!(v2.support_lbr_stack());
+        assert_eq!(v2.lbr_stack_size(), 16);
+        assert_eq!(v2.df_counters(), 4);
+        assert_eq!(v2.umc_counters(), 1);
+    }
+}
@@ -0,0 +1,505 @@
+use core::fmt::Display;
+
+use register::RegisterBufferReader;
+
+use super::{
+    feature::{ext_fields, structured_fields},
+    CpuidSource,
+};
+
+/// 可以声明为启动条件的处理器特性
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feature {
+    Fpu,
+    Tsc,
+    Msr,
+    Pae,
+    Cx8,
+    Apic,
+    Pge,
+    Cmov,
+    Pat,
+    Clflush,
+    Mmx,
+    Fxsr,
+    Sse,
+    Sse2,
+    Sse3,
+    Ssse3,
+    Fma,
+    Cx16,
+    Pcid,
+    Sse4_1,
+    Sse4_2,
+    X2apic,
+    Movbe,
+    Popcnt,
+    TscDeadline,
+    Aes,
+    Xsave,
+    Osxsave,
+    Avx,
+    F16c,
+    Rdrand,
+    Fsgsbase,
+    Bmi1,
+    Avx2,
+    Smep,
+    Bmi2,
+    Invpcid,
+    Avx512f,
+    Avx512dq,
+    Rdseed,
+    Smap,
+    Avx512cd,
+    Avx512bw,
+    Avx512vl,
+    Umip,
+    La57,
+    LahfLm,
+    Lzcnt,
+    Syscall,
+    Nx,
+    Page1Gb,
+    Rdtscp,
+    LongMode,
+}
+
+impl Feature {
+    pub const ALL: [Feature; 53] = [
+        Feature::Fpu,
+        Feature::Tsc,
+        Feature::Msr,
+        Feature::Pae,
+        Feature::Cx8,
+        Feature::Apic,
+        Feature::Pge,
+        Feature::Cmov,
+        Feature::Pat,
+        Feature::Clflush,
+        Feature::Mmx,
+        Feature::Fxsr,
+        Feature::Sse,
+        Feature::Sse2,
+        Feature::Sse3,
+        Feature::Ssse3,
+        Feature::Fma,
+        Feature::Cx16,
+        Feature::Pcid,
+        Feature::Sse4_1,
+        Feature::Sse4_2,
+        Feature::X2apic,
+        Feature::Movbe,
+        Feature::Popcnt,
+        Feature::TscDeadline,
+        Feature::Aes,
+        Feature::Xsave,
+        Feature::Osxsave,
+        Feature::Avx,
+        Feature::F16c,
+        Feature::Rdrand,
+        Feature::Fsgsbase,
+        Feature::Bmi1,
+        Feature::Avx2,
+        Feature::Smep,
+        Feature::Bmi2,
+        Feature::Invpcid,
+        Feature::Avx512f,
+        Feature::Avx512dq,
+        Feature::Rdseed,
+        Feature::Smap,
+        Feature::Avx512cd,
+        Feature::Avx512bw,
+        Feature::Avx512vl,
+        Feature::Umip,
+        Feature::La57,
+        Feature::LahfLm,
+        Feature::Lzcnt,
+        Feature::Syscall,
+        Feature::Nx,
+        Feature::Page1Gb,
+        Feature::Rdtscp,
+        Feature::LongMode,
+    ];
+
+    /// 与 `/proc/cpuinfo` 中 flags 一致的名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            Feature::Fpu => "fpu",
+            Feature::Tsc => "tsc",
+            Feature::Msr => "msr",
+            Feature::Pae => "pae",
+            Feature::Cx8 => "cx8",
+            Feature::Apic => "apic",
+            Feature::Pge => "pge",
+            Feature::Cmov => "cmov",
+            Feature::Pat => "pat",
+            Feature::Clflush => "clflush",
+            Feature::Mmx => "mmx",
+            Feature::Fxsr => "fxsr",
+            Feature::Sse => "sse",
+            Feature::Sse2 => "sse2",
+            Feature::Sse3 => "pni",
+            Feature::Ssse3 => "ssse3",
+            Feature::Fma => "fma",
+            Feature::Cx16 => "cx16",
+            Feature::Pcid => "pcid",
+            Feature::Sse4_1 => "sse4_1",
+            Feature::Sse4_2 => "sse4_2",
+            Feature::X2apic => "x2apic",
+            Feature::Movbe => "movbe",
+            Feature::Popcnt => "popcnt",
+            Feature::TscDeadline => "tsc_deadline_timer",
+            Feature::Aes => "aes",
+            Feature::Xsave => "xsave",
+            Feature::Osxsave => "osxsave",
+            Feature::Avx => "avx",
+            Feature::F16c => "f16c",
+            Feature::Rdrand => "rdrand",
+            Feature::Fsgsbase => "fsgsbase",
+            Feature::Bmi1 => "bmi1",
+            Feature::Avx2 => "avx2",
+            Feature::Smep => "smep",
+            Feature::Bmi2 => "bmi2",
+            Feature::Invpcid => "invpcid",
+            Feature::Avx512f => "avx512f",
+            Feature::Avx512dq => "avx512dq",
+            Feature::Rdseed => "rdseed",
+            Feature::Smap => "smap",
+            Feature::Avx512cd => "avx512cd",
+            Feature::Avx512bw => "avx512bw",
+            Feature::Avx512vl => "avx512vl",
+            Feature::Umip => "umip",
+            Feature::La57 => "la57",
+            Feature::LahfLm => "lahf_lm",
+            Feature::Lzcnt => "abm",
+            Feature::Syscall => "syscall",
+            Feature::Nx => "nx",
+            Feature::Page1Gb => "pdpe1gb",
+            Feature::Rdtscp => "rdtscp",
+            Feature::LongMode => "lm",
+        }
+    }
+
+    const fn bit(self) -> u64 {
+        1 << self as u64
+    }
+}
+
+impl Display for Feature {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// # 特性集合
+///
+/// 可以通过 [`FeatureSet::with`] 在常量中声明自定义的特性集合：
+///
+/// ```not test
+/// const KERNEL_REQUIREMENTS: FeatureSet = MicroArchLevel::V2
+///     .features()
+///     .with(Feature::Pcid)
+///     .with(Feature::Smep);
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FeatureSet {
+    bits: u64,
+}
+
+impl FeatureSet {
+    pub const fn empty() -> Self {
+        Self { bits: 0 }
+    }
+    #[must_use]
+    pub const fn with(self, feature: Feature) -> Self {
+        Self {
+            bits: self.bits | feature.bit(),
+        }
+    }
+    #[must_use]
+    pub const fn union(self, other: FeatureSet) -> Self {
+        Self {
+            bits: self.bits | other.bits,
+        }
+    }
+    /// 在 `self` 中但不在 `other` 中的特性
+    #[must_use]
+    pub const fn difference(self, other: FeatureSet) -> Self {
+        Self {
+            bits: self.bits & !other.bits,
+        }
+    }
+    pub fn from_features(features: &[Feature]) -> Self {
+        features
+            .iter()
+            .fold(Self::empty(), |set, &feature| set.with(feature))
+    }
+    pub fn insert(&mut self, feature: Feature) {
+        self.bits |= feature.bit();
+    }
+    pub fn contains(&self, feature: Feature) -> bool {
+        self.bits & feature.bit() != 0
+    }
+    pub fn is_empty(&self) -> bool {
+        self.bits == 0
+    }
+    pub fn len(&self) -> usize {
+        self.bits.count_ones() as usize
+    }
+    /// 按 [`Feature::ALL`] 的顺序遍历集合中的特性
+    pub fn iter(&self) -> impl Iterator<Item = Feature> + '_ {
+        Feature::ALL
+            .iter()
+            .copied()
+            .filter(move |&feature| self.contains(feature))
+    }
+
+    /// 处理器所支持的全部特性
+    pub fn detect<S: CpuidSource + ?Sized>(cpuid: &S) -> Self {
+        let std = cpuid.std_feature();
+        let structured = cpuid.structured_ext_feature();
+        let ext = cpuid.ext_feature();
+        let mut set = Self::empty();
+        for &feature in Feature::ALL.iter() {
+            let present = match feature {
+                Feature::Fpu => std.support_fpu(),
+                Feature::Tsc => std.support_tsc(),
+                Feature::Msr => std.support_msr(),
+                Feature::Pae => std.support_pae(),
+                Feature::Cx8 => std.support_cx8(),
+                Feature::Apic => std.support_apic(),
+                Feature::Pge => std.support_pge(),
+                Feature::Cmov => std.support_cmov(),
+                Feature::Pat => std.support_pat(),
+                Feature::Clflush => std.support_clflush(),
+                Feature::Mmx => std.support_mmx(),
+                Feature::Fxsr => std.support_fxsr(),
+                Feature::Sse => std.support_sse(),
+                Feature::Sse2 => std.support_sse2(),
+                Feature::Sse3 => std.support_sse3(),
+                Feature::Ssse3 => std.support_ssse3(),
+                Feature::Fma => std.support_fma(),
+                Feature::Cx16 => std.support_cx16(),
+                Feature::Pcid => std.support_pcid(),
+                Feature::Sse4_1 => std.support_sse4_1(),
+                Feature::Sse4_2 => std.support_sse4_2(),
+                Feature::X2apic => std.support_x2apic(),
+                Feature::Movbe => std.support_movbe(),
+                Feature::Popcnt => std.support_popcnt(),
+                Feature::TscDeadline => std.support_tsc_deadline(),
+                Feature::Aes => std.support_aes(),
+                Feature::Xsave => std.support_xsave(),
+                Feature::Osxsave => std.osxsave_enabled(),
+                Feature::Avx => std.support_avx(),
+                Feature::F16c => std.support_f16c(),
+                Feature::Rdrand => std.support_rdrand(),
+                Feature::Fsgsbase => structured.support_fsgsbase(),
+                Feature::Bmi1 => structured.read::<structured_fields::BMI1>(),
+                Feature::Avx2 => structured.support_avx2(),
+                Feature::Smep => structured.support_smep(),
+                Feature::Bmi2 => structured.read::<structured_fields::BMI2>(),
+                Feature::Invpcid => structured.support_invpcid(),
+                Feature::Avx512f => structured.support_avx512f(),
+                Feature::Avx512dq => structured.read::<structured_fields::AVX512DQ>(),
+                Feature::Rdseed => structured.read::<structured_fields::RDSEED>(),
+                Feature::Smap => structured.support_smap(),
+                Feature::Avx512cd => structured.read::<structured_fields::AVX512CD>(),
+                Feature::Avx512bw => structured.read::<structured_fields::AVX512BW>(),
+                Feature::Avx512vl => structured.read::<structured_fields::AVX512VL>(),
+                Feature::Umip => structured.support_umip(),
+                Feature::La57 => structured.support_la57(),
+                Feature::LahfLm => ext.support_lahf_lm(),
+                Feature::Lzcnt => ext.read::<ext_fields::ABM>(),
+                Feature::Syscall => ext.support_syscall(),
+                Feature::Nx => ext.support_nx(),
+                Feature::Page1Gb => ext.support_page1gb(),
+                Feature::Rdtscp => ext.support_rdtscp(),
+                Feature::LongMode => ext.support_long_mode(),
+            };
+            if present {
+                set.insert(feature);
+            }
+        }
+        set
+    }
+
+    /// 检查处理器是否满足本集合中的所有特性
+    pub fn check<S: CpuidSource + ?Sized>(&self, cpuid: &S) -> RequirementReport {
+        RequirementReport {
+            required: *self,
+            missing: self.difference(Self::detect(cpuid)),
+        }
+    }
+}
+
+impl Display for FeatureSet {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for (i, feature) in self.iter().enumerate() {
+            if i != 0 {
+                f.write_str(" ")?;
+            }
+            f.write_str(feature.name())?;
+        }
+        Ok(())
+    }
+}
+
+/// # x86-64 微架构级别
+///
+/// 由 x86-64 psABI 定义，每个级别均包含上一级别的所有特性。
+///
+/// ❗ V3、V4 中的 AVX、AVX-512 还需要操作系统通过 XCR0 使能对应的状态组件，
+/// 这里只能检查 `OSXSAVE`，无法检查 XCR0。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MicroArchLevel {
+    /// CMOV、CX8、FPU、FXSR、MMX、SCE、SSE、SSE2，以及 long 模式
+    V1,
+    /// CMPXCHG16B、LAHF-SAHF、POPCNT、SSE3、SSE4.1、SSE4.2、SSSE3
+    V2,
+    /// AVX、AVX2、BMI1、BMI2、F16C、FMA、LZCNT、MOVBE、OSXSAVE
+    V3,
+    /// AVX512F、AVX512BW、AVX512CD、AVX512DQ、AVX512VL
+    V4,
+}
+
+impl MicroArchLevel {
+    const V1_FEATURES: FeatureSet = FeatureSet::empty()
+        .with(Feature::Cmov)
+        .with(Feature::Cx8)
+        .with(Feature::Fpu)
+        .with(Feature::Fxsr)
+        .with(Feature::Mmx)
+        .with(Feature::Syscall)
+        .with(Feature::Sse)
+        .with(Feature::Sse2)
+        .with(Feature::LongMode);
+    const V2_FEATURES: FeatureSet = Self::V1_FEATURES
+        .with(Feature::Cx16)
+        .with(Feature::LahfLm)
+        .with(Feature::Popcnt)
+        .with(Feature::Sse3)
+        .with(Feature::Sse4_1)
+        .with(Feature::Sse4_2)
+        .with(Feature::Ssse3);
+    const V3_FEATURES: FeatureSet = Self::V2_FEATURES
+        .with(Feature::Avx)
+        .with(Feature::Avx2)
+        .with(Feature::Bmi1)
+        .with(Feature::Bmi2)
+        .with(Feature::F16c)
+        .with(Feature::Fma)
+        .with(Feature::Lzcnt)
+        .with(Feature::Movbe)
+        .with(Feature::Osxsave);
+    const V4_FEATURES: FeatureSet = Self::V3_FEATURES
+        .with(Feature::Avx512f)
+        .with(Feature::Avx512bw)
+        .with(Feature::Avx512cd)
+        .with(Feature::Avx512dq)
+        .with(Feature::Avx512vl);
+
+    /// 该级别要求的全部特性（包含更低级别的特性）
+    pub const fn features(self) -> FeatureSet {
+        match self {
+            MicroArchLevel::V1 => Self::V1_FEATURES,
+            MicroArchLevel::V2 => Self::V2_FEATURES,
+            MicroArchLevel::V3 => Self::V3_FEATURES,
+            MicroArchLevel::V4 => Self::V4_FEATURES,
+        }
+    }
+
+    /// 处理器所满足的最高级别，若连 V1 都不满足，则返回 None。
+    pub fn detect<S: CpuidSource + ?Sized>(cpuid: &S) -> Option<Self> {
+        let present = FeatureSet::detect(cpuid);
+        [
+            MicroArchLevel::V4,
+            MicroArchLevel::V3,
+            MicroArchLevel::V2,
+            MicroArchLevel::V1,
+        ]
+        .iter()
+        .copied()
+        .find(|level| level.features().difference(present).is_empty())
+    }
+}
+
+/// # 特性检查结果
+///
+/// `Display` 会输出可以直接打印给用户的错误信息，例如：
+///
+/// ```text
+/// missing required CPU features: avx512f avx512dq avx512cd avx512bw avx512vl
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequirementReport {
+    required: FeatureSet,
+    missing: FeatureSet,
+}
+
+impl RequirementReport {
+    pub fn is_satisfied(&self) -> bool {
+        self.missing.is_empty()
+    }
+    pub fn required(&self) -> FeatureSet {
+        self.required
+    }
+    /// 缺少的特性
+    pub fn missing(&self) -> FeatureSet {
+        self.missing
+    }
+}
+
+impl Display for RequirementReport {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if self.is_satisfied() {
+            write!(
+                f,
+                "all {} required CPU features present",
+                self.required.len()
+            )
+        } else {
+            write!(f, "missing required CPU features: {}", self.missing)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::format;
+
+    use crate::cpuid::{table::CpuidTable, CpuidSource};
+
+    use super::{Feature, FeatureSet, MicroArchLevel};
+
+    const COFFEE_LAKE: &str = include_str!("testdata/coffee_lake.txt");
+
+    #[test]
+    fn micro_arch_level() {
+        let table = CpuidTable::parse(COFFEE_LAKE).unwrap();
+        assert_eq!(MicroArchLevel::detect(&table), Some(MicroArchLevel::V3));
+        assert!(table
+            .check_requirements(MicroArchLevel::V3.features())
+            .is_satisfied());
+
+        let report = table.check_requirements(MicroArchLevel::V4.features());
+        assert!(!report.is_satisfied());
+        assert_eq!(report.missing().len(), 5);
+        assert_eq!(
+            format!("{}", report),
+            "missing required CPU features: avx512f avx512dq avx512cd avx512bw avx512vl"
+        );
+    }
+
+    #[test]
+    fn custom_requirements() {
+        let table = CpuidTable::parse(COFFEE_LAKE).unwrap();
+        let required = FeatureSet::from_features(&[Feature::Pcid, Feature::Smep, Feature::La57]);
+        let report = required.check(&table);
+        assert_eq!(report.missing(), FeatureSet::empty().with(Feature::La57));
+        assert!(report.required().contains(Feature::Smep));
+
+        // 空的 CPUID 表不满足任何级别
+        assert_eq!(MicroArchLevel::detect(&CpuidTable::new()), None);
+    }
+}
@@ -0,0 +1,103 @@
+use core::fmt::Display;
+
+/// # 处理器签名
+///
+/// 由 `CPUID.(EAX=01h):EAX` 解码而来，其中：
+///
+/// + 当 Family 为 0Fh 时，显示的 family 为 `Family + ExtendedFamily`；
+/// + 当 Family 为 06h 或 0Fh 时，显示的 model 为 `(ExtendedModel << 4) + Model`；
+///
+/// AMD 手册中仅在 Family 为 0Fh 时使用 ExtendedModel，
+/// 但 AMD 处理器在 Family 小于 0Fh 时 ExtendedModel 均为 0，两者计算结果一致。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CpuSignature {
+    pub(crate) eax: u32,
+}
+
+impl CpuSignature {
+    pub fn from_raw(eax: u32) -> Self {
+        Self { eax }
+    }
+    pub fn raw(&self) -> u32 {
+        self.eax
+    }
+    pub fn stepping(&self) -> u8 {
+        (self.eax & 0xf) as u8
+    }
+    /// 未经扩展的 model 字段
+    pub fn base_model(&self) -> u8 {
+        ((self.eax >> 4) & 0xf) as u8
+    }
+    /// 未经扩展的 family 字段
+    pub fn base_family(&self) -> u8 {
+        ((self.eax >> 8) & 0xf) as u8
+    }
+    /// 处理器类型，仅 Intel 处理器使用：0 为 OEM 处理器，1 为 OverDrive，2 为双处理器。
+    pub fn processor_type(&self) -> u8 {
+        ((self.eax >> 12) & 0x3) as u8
+    }
+    pub fn extended_model(&self) -> u8 {
+        ((self.eax >> 16) & 0xf) as u8
+    }
+    pub fn extended_family(&self) -> u8 {
+        ((self.eax >> 20) & 0xff) as u8
+    }
+    /// 显示的 family（DisplayFamily）
+    pub fn family(&self) -> u16 {
+        let family = self.base_family() as u16;
+        if family == 0x0f {
+            family + self.extended_family() as u16
+        } else {
+            family
+        }
+    }
+    /// 显示的 model（DisplayModel）
+    pub fn model(&self) -> u8 {
+        match self.base_family() {
+            0x06 | 0x0f => (self.extended_model() << 4) | self.base_model(),
+            _ => self.base_model(),
+        }
+    }
+}
+
+impl Display for CpuSignature {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "family {:#x} model {:#x} stepping {:#x}",
+            self.family(),
+            self.model(),
+            self.stepping()
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::CpuSignature;
+
+    #[test]
+    fn display_family_model() {
+        // Intel Skylake-S
+        let sig = CpuSignature::from_raw(0x0005_06e3);
+        assert_eq!(sig.family(), 0x06);
+        assert_eq!(sig.model(), 0x5e);
+        assert_eq!(sig.stepping(), 3);
+
+        // AMD Zen 2 (Matisse)
+        let sig = CpuSignature::from_raw(0x0087_0f10);
+        assert_eq!(sig.family(), 0x17);
+        assert_eq!(sig.model(), 0x71);
+        assert_eq!(sig.stepping(), 0);
+
+        // Intel Pentium 4 (Prescott)，family 为 0Fh 且无扩展 family
+        let sig = CpuSignature::from_raw(0x0000_0f41);
+        assert_eq!(sig.family(), 0x0f);
+        assert_eq!(sig.model(), 0x04);
+
+        // family 为 05h 时，忽略扩展 model
+        let sig = CpuSignature::from_raw(0x0001_0543);
+        assert_eq!(sig.family(), 0x05);
+        assert_eq!(sig.model(), 0x04);
+    }
+}
@@ -0,0 +1,453 @@
+use core::fmt::Display;
+
+use super::{
+    hypervisor::Hypervisor,
+    table::{CpuidTable, CpuidTableError},
+    CpuidResult, CpuidSource,
+};
+
+/// CPUID 结果中的寄存器
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CpuidRegister {
+    Eax,
+    Ebx,
+    Ecx,
+    Edx,
+}
+
+impl CpuidRegister {
+    fn of(self, result: &CpuidResult) -> u32 {
+        match self {
+            CpuidRegister::Eax => result.eax,
+            CpuidRegister::Ebx => result.ebx,
+            CpuidRegister::Ecx => result.ecx,
+            CpuidRegister::Edx => result.edx,
+        }
+    }
+    fn of_mut(self, result: &mut CpuidResult) -> &mut u32 {
+        match self {
+            CpuidRegister::Eax => &mut result.eax,
+            CpuidRegister::Ebx => &mut result.ebx,
+            CpuidRegister::Ecx => &mut result.ecx,
+            CpuidRegister::Edx => &mut result.edx,
+        }
+    }
+}
+
+/// 由特性位组成的寄存器
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeatureWord {
+    pub leaf: u32,
+    pub sub_leaf: u32,
+    pub register: CpuidRegister,
+}
+
+impl Display for FeatureWord {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let register = match self.register {
+            CpuidRegister::Eax => "eax",
+            CpuidRegister::Ebx => "ebx",
+            CpuidRegister::Ecx => "ecx",
+            CpuidRegister::Edx => "edx",
+        };
+        write!(f, "{:#010x}.{:#x}:{}", self.leaf, self.sub_leaf, register)
+    }
+}
+
+const fn word(leaf: u32, sub_leaf: u32, register: CpuidRegister) -> FeatureWord {
+    FeatureWord {
+        leaf,
+        sub_leaf,
+        register,
+    }
+}
+
+/// 特性字中的一组 bit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeatureBits {
+    pub word: FeatureWord,
+    pub bits: u32,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SnapshotError {
+    /// 输出缓冲区不足，至少需要 `required` 字节
+    BufferTooSmall {
+        required: usize,
+    },
+    InvalidMagic,
+    UnsupportedVersion(u8),
+    /// 数据在第 `offset` 字节处提前结束或格式错误
+    Malformed {
+        offset: usize,
+    },
+    /// 超出了 [`CpuidTable::CAPACITY`]
+    CapacityExceeded,
+}
+
+impl From<CpuidTableError> for SnapshotError {
+    fn from(_: CpuidTableError) -> Self {
+        SnapshotError::CapacityExceeded
+    }
+}
+
+/// # CPUID 快照
+///
+/// 记录处理器所有有效的功能号和子功能号，用于在虚拟机热迁移前比较源主机与目标主机的特性。
+///
+/// ## 字节格式
+///
+/// 所有整数均为小端序：
+///
+/// ```text
+/// header: magic "CPID" (4) | version (1) | reserved 0 (1) | entry count (u16)
+/// entry:  leaf (u32) | sub-leaf (u32) | mask (u8) | 寄存器 (mask 中每个为 1 的 bit 对应一个 u32)
+/// ```
+///
+/// mask 的 bit 0..=3 分别对应 EAX、EBX、ECX、EDX，值为 0 的寄存器不会写入。
+/// 条目按功能号、子功能号升序排列，因此相同的快照总是得到相同的字节序列。
+///
+/// ## 比较
+///
+/// 比较只作用于 [`FEATURE_WORDS`](Self::FEATURE_WORDS) 中的特性字，
+/// 其他寄存器（例如签名、缓存参数、APIC ID）在不同主机之间本来就不同，不参与比较。
+#[derive(Clone)]
+pub struct CpuidSnapshot {
+    table: CpuidTable,
+}
+
+impl CpuidSnapshot {
+    pub const MAGIC: [u8; 4] = *b"CPID";
+    pub const VERSION: u8 = 1;
+    const HEADER_SIZE: usize = 8;
+    const ENTRY_HEADER_SIZE: usize = 9;
+    /// 遍历子功能号时的上限，与 XSAVE 状态组件的数量一致
+    const MAX_SUB_LEAF: u32 = 64;
+
+    /// 参与比较的特性字
+    pub const FEATURE_WORDS: [FeatureWord; 21] = [
+        word(0x01, 0, CpuidRegister::Ecx),
+        word(0x01, 0, CpuidRegister::Edx),
+        word(0x06, 0, CpuidRegister::Eax),
+        word(0x06, 0, CpuidRegister::Ecx),
+        word(0x07, 0, CpuidRegister::Ebx),
+        word(0x07, 0, CpuidRegister::Ecx),
+        word(0x07, 0, CpuidRegister::Edx),
+        word(0x07, 1, CpuidRegister::Eax),
+        word(0x07, 1, CpuidRegister::Edx),
+        word(0x07, 2, CpuidRegister::Edx),
+        // XCR0、IA32_XSS 所支持的状态组件
+        word(0x0d, 0, CpuidRegister::Eax),
+        word(0x0d, 0, CpuidRegister::Edx),
+        word(0x0d, 1, CpuidRegister::Eax),
+        word(0x0d, 1, CpuidRegister::Ecx),
+        word(0x0d, 1, CpuidRegister::Edx),
+        word(0x8000_0001, 0, CpuidRegister::Ecx),
+        word(0x8000_0001, 0, CpuidRegister::Edx),
+        word(0x8000_0007, 0, CpuidRegister::Edx),
+        word(0x8000_0008, 0, CpuidRegister::Ebx),
+        word(0x8000_001f, 0, CpuidRegister::Eax),
+        word(0x8000_0021, 0, CpuidRegister::Eax),
+    ];
+
+    /// 记录所有有效的基本功能号、每个虚拟机监视器接口（参见 [`Hypervisor`]）的功能号和扩展功能号。
+    ///
+    /// 对于使用子功能号的功能号，记录 [`CpuidSource::query_checked`] 认为有效、且结果不全为 0 的子功能号，
+    /// 子功能号 0 总会被记录。
+    pub fn capture<S: CpuidSource + ?Sized>(cpuid: &S) -> Result<Self, SnapshotError> {
+        let mut table = CpuidTable::new();
+        // 每个接口只记录其起始功能号之后的 0x100 个功能号，避免与下一个接口重叠
+        let hypervisor_leaves = Hypervisor::detect_all(cpuid).flat_map(|hypervisor| {
+            hypervisor.base()..=hypervisor.max_leaf().min(hypervisor.base() + 0xff)
+        });
+        let ext_leaves = Some(cpuid.max_ext_leaf())
+            .filter(|&max_leaf| max_leaf != 0)
+            .map(|max_leaf| 0x8000_0000..=max_leaf);
+        for leaf in (0..=cpuid.max_basic_leaf())
+            .chain(hypervisor_leaves)
+            .chain(ext_leaves.into_iter().flatten())
+        {
+            if !CpuidTable::has_sub_leaf(leaf) {
+                if let Ok(result) = cpuid.query_checked(leaf, 0) {
+                    table.insert(leaf, 0, result)?;
+                }
+                continue;
+            }
+            for sub_leaf in 0..Self::MAX_SUB_LEAF {
+                if let Ok(result) = cpuid.query_checked(leaf, sub_leaf) {
+                    if sub_leaf == 0 || result != CpuidResult::default() {
+                        table.insert(leaf, sub_leaf, result)?;
+                    }
+                }
+            }
+        }
+        Ok(Self::from_table(table))
+    }
+
+    /// 条目会按功能号、子功能号重新排序
+    pub fn from_table(mut table: CpuidTable) -> Self {
+        table.sort();
+        Self { table }
+    }
+    pub fn table(&self) -> &CpuidTable {
+        &self.table
+    }
+
+    fn registers(result: &CpuidResult) -> [u32; 4] {
+        [result.eax, result.ebx, result.ecx, result.edx]
+    }
+
+    /// 序列化后的字节数
+    pub fn encoded_len(&self) -> usize {
+        Self::HEADER_SIZE
+            + self
+                .table
+                .entries()
+                .iter()
+                .map(|entry| {
+                    let non_zero = Self::registers(&entry.result)
+                        .iter()
+                        .filter(|&&reg| reg != 0)
+                        .count();
+                    Self::ENTRY_HEADER_SIZE + non_zero * 4
+                })
+                .sum::<usize>()
+    }
+
+    /// 序列化到 `buf` 中，返回写入的字节数。
+    pub fn write_to(&self, buf: &mut [u8]) -> Result<usize, SnapshotError> {
+        let required = self.encoded_len();
+        if buf.len() < required {
+            return Err(SnapshotError::BufferTooSmall { required });
+        }
+        let entries = self.table.entries();
+        buf[..4].copy_from_slice(&Self::MAGIC);
+        buf[4] = Self::VERSION;
+        buf[5] = 0;
+        buf[6..8].copy_from_slice(&(entries.len() as u16).to_le_bytes());
+        let mut offset = Self::HEADER_SIZE;
+        for entry in entries {
+            buf[offset..offset + 4].copy_from_slice(&entry.leaf.to_le_bytes());
+            buf[offset + 4..offset + 8].copy_from_slice(&entry.sub_leaf.to_le_bytes());
+            let mask_offset = offset + 8;
+            let mut mask = 0u8;
+            offset += Self::ENTRY_HEADER_SIZE;
+            for (i, reg) in Self::registers(&entry.result).iter().enumerate() {
+                if *reg != 0 {
+                    mask |= 1 << i;
+                    buf[offset..offset + 4].copy_from_slice(&reg.to_le_bytes());
+                    offset += 4;
+                }
+            }
+            buf[mask_offset] = mask;
+        }
+        Ok(offset)
+    }
+
+    /// 解析 [`write_to`](Self::write_to) 写入的字节序列，不允许存在多余的字节。
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        if bytes.len() < Self::HEADER_SIZE {
+            return Err(SnapshotError::Malformed {
+                offset: bytes.len(),
+            });
+        }
+        if bytes[..4] != Self::MAGIC {
+            return Err(SnapshotError::InvalidMagic);
+        }
+        if bytes[4] != Self::VERSION {
+            return Err(SnapshotError::UnsupportedVersion(bytes[4]));
+        }
+        if bytes[5] != 0 {
+            return Err(SnapshotError::Malformed { offset: 5 });
+        }
+        let count = u16::from_le_bytes([bytes[6], bytes[7]]) as usize;
+        if count > CpuidTable::CAPACITY {
+            return Err(SnapshotError::CapacityExceeded);
+        }
+
+        let mut offset = Self::HEADER_SIZE;
+        let read_u32 = |offset: usize| -> Result<u32, SnapshotError> {
+            bytes
+                .get(offset..offset + 4)
+                .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .ok_or(SnapshotError::Malformed { offset })
+        };
+        let mut table = CpuidTable::new();
+        for _ in 0..count {
+            let leaf = read_u32(offset)?;
+            let sub_leaf = read_u32(offset + 4)?;
+            let mask = *bytes
+                .get(offset + 8)
+                .ok_or(SnapshotError::Malformed { offset: offset + 8 })?;
+            if mask & !0b1111 != 0 {
+                return Err(SnapshotError::Malformed { offset: offset + 8 });
+            }
+            offset += Self::ENTRY_HEADER_SIZE;
+            let mut registers = [0u32; 4];
+            for (i, reg) in registers.iter_mut().enumerate() {
+                if mask & (1 << i) != 0 {
+                    *reg = read_u32(offset)?;
+                    offset += 4;
+                }
+            }
+            let result = CpuidResult {
+                eax: registers[0],
+                ebx: registers[1],
+                ecx: registers[2],
+                edx: registers[3],
+            };
+            table.insert(leaf, sub_leaf, result)?;
+        }
+        if offset != bytes.len() {
+            return Err(SnapshotError::Malformed { offset });
+        }
+        Ok(Self::from_table(table))
+    }
+
+    /// 特性字的值，若快照中没有对应的功能号，则返回 0。
+    pub fn feature_bits(&self, word: FeatureWord) -> u32 {
+        self.table
+            .get(word.leaf, word.sub_leaf)
+            .map_or(0, |result| word.register.of(result))
+    }
+
+    /// 两个快照共同支持的特性：特性字按位与，其他寄存器保持 `self` 中的值。
+    ///
+    /// 可用于计算一组主机的公共特性，作为虚拟机的 CPUID 模型。
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut table = CpuidTable::new();
+        for entry in self.table.entries() {
+            let mut result = entry.result;
+            for word in Self::FEATURE_WORDS.iter() {
+                if word.leaf == entry.leaf && word.sub_leaf == entry.sub_leaf {
+                    *word.register.of_mut(&mut result) &= other.feature_bits(*word);
+                }
+            }
+            // 条目数量不会超过 self 的条目数量
+            let _ = table.insert(entry.leaf, entry.sub_leaf, result);
+        }
+        Self { table }
+    }
+
+    /// `self` 支持而 `other` 不支持的特性位
+    pub fn difference<'a>(&'a self, other: &'a Self) -> impl Iterator<Item = FeatureBits> + 'a {
+        Self::FEATURE_WORDS.iter().filter_map(move |&word| {
+            let bits = self.feature_bits(word) & !other.feature_bits(word);
+            if bits == 0 {
+                None
+            } else {
+                Some(FeatureBits { word, bits })
+            }
+        })
+    }
+
+    /// `self` 的所有特性在 `other` 中均受支持，即在 `self` 上启动的虚拟机可以迁移到 `other` 上。
+    pub fn is_subset_of(&self, other: &Self) -> bool {
+        self.difference(other).next().is_none()
+    }
+}
+
+impl CpuidSource for CpuidSnapshot {
+    fn query(&self, leaf: usize, sub_leaf: usize) -> CpuidResult {
+        self.table.query(leaf, sub_leaf)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cpuid::{table::CpuidTable, CpuidSource};
+
+    use super::{CpuidRegister, CpuidSnapshot, FeatureBits, SnapshotError};
+
+    fn coffee_lake() -> CpuidSnapshot {
+        let table = CpuidTable::parse(include_str!("testdata/coffee_lake.txt")).unwrap();
+        CpuidSnapshot::capture(&table).unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        let snapshot = coffee_lake();
+        assert_eq!(snapshot.max_basic_leaf(), 0x16);
+        assert_eq!(snapshot.table().get(0x04, 3).map(|r| r.ecx), Some(0x2fff));
+        assert!(snapshot.table().get(0x04, 4).is_none());
+        assert!(snapshot.table().get(0x0d, 8).is_some());
+
+        let mut buf = [0u8; 4096];
+        let len = snapshot.write_to(&mut buf).unwrap();
+        assert_eq!(len, snapshot.encoded_len());
+        assert_eq!(&buf[..5], b"CPID\x01");
+        let parsed = CpuidSnapshot::from_bytes(&buf[..len]).unwrap();
+        assert_eq!(parsed.table().entries(), snapshot.table().entries());
+
+        let mut again = [0u8; 4096];
+        assert_eq!(parsed.write_to(&mut again), Ok(len));
+        assert_eq!(buf[..len], again[..len]);
+
+        assert_eq!(
+            snapshot.write_to(&mut again[..16]),
+            Err(SnapshotError::BufferTooSmall { required: len })
+        );
+        assert_eq!(
+            CpuidSnapshot::from_bytes(&buf[..len - 1]).err(),
+            Some(SnapshotError::Malformed { offset: len - 4 })
+        );
+        buf[5] = 1;
+        assert_eq!(
+            CpuidSnapshot::from_bytes(&buf[..len]).err(),
+            Some(SnapshotError::Malformed { offset: 5 })
+        );
+        buf[4] = 2;
+        assert_eq!(
+            CpuidSnapshot::from_bytes(&buf[..len]).err(),
+            Some(SnapshotError::UnsupportedVersion(2))
+        );
+    }
+
+    #[test]
+    fn feature_difference() {
+        let source = coffee_lake();
+        // 目标主机不支持 AVX2（07h.EBX[5]）
+        let mut table = source.table().clone();
+        let mut leaf7 = *table.get(0x07, 0).unwrap();
+        leaf7.ebx &= !(1 << 5);
+        table.insert(0x07, 0, leaf7).unwrap();
+        let target = CpuidSnapshot::from_table(table);
+
+        let mut difference = source.difference(&target);
+        let FeatureBits { word, bits } = difference.next().unwrap();
+        assert_eq!(
+            (word.leaf, word.sub_leaf, word.register, bits),
+            (0x07, 0, CpuidRegister::Ebx, 1 << 5)
+        );
+        assert!(difference.next().is_none());
+        assert!(!source.is_subset_of(&target));
+        assert!(target.is_subset_of(&source));
+
+        let common = source.intersection(&target);
+        assert!(common.is_subset_of(&target) && common.is_subset_of(&source));
+        assert!(!common.structured_ext_feature().support_avx2());
+        assert!(common.structured_ext_feature().support_smep());
+    }
+
+    #[test]
+    fn capture_every_hypervisor() {
+        // KVM 使能 Hyper-V 兼容接口后，KVM 的签名位于 4000_0100h
+        let table = CpuidTable::parse(
+            "
+            0x00000000 0x00: eax=0x00000001 ebx=0x00000000 ecx=0x00000000 edx=0x00000000
+            0x00000001 0x00: eax=0x00000000 ebx=0x00000000 ecx=0x80000000 edx=0x00000000
+            0x40000000 0x00: eax=0x4000000a ebx=0x7263694d ecx=0x666f736f edx=0x76482074
+            0x40000001 0x00: eax=0x31237648 ebx=0x00000000 ecx=0x00000000 edx=0x00000000
+            0x40000100 0x00: eax=0x40000101 ebx=0x4b4d564b ecx=0x564b4d56 edx=0x0000004d
+            0x40000101 0x00: eax=0x01000ef9 ebx=0x00000000 ecx=0x00000000 edx=0x00000000
+            ",
+        )
+        .unwrap();
+        assert!(table.kvm_feature().unwrap().support_pv_eoi());
+
+        let snapshot = CpuidSnapshot::capture(&table).unwrap();
+        assert_eq!(snapshot.table().entries().len(), 2 + 11 + 2);
+        assert!(snapshot.hyperv_feature().is_some());
+        assert!(snapshot.kvm_feature().unwrap().support_pv_eoi());
+    }
+}
@@ -0,0 +1,272 @@
+use super::{CpuidResult, CpuidSource};
+
+/// 记录的一条 CPUID 数据
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CpuidEntry {
+    pub leaf: u32,
+    pub sub_leaf: u32,
+    pub result: CpuidResult,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CpuidTableError {
+    /// 超出了 [`CpuidTable::CAPACITY`]
+    CapacityExceeded,
+    /// 第 `line` 行（从 1 开始）无法解析
+    Syntax { line: usize },
+}
+
+/// # 内存中的 CPUID 数据表
+///
+/// 用于在宿主机上重放事先记录的 CPUID 数据，从而测试依赖于处理器特性的逻辑。
+///
+/// 查询时的行为：
+///
+/// + 若存在完全匹配的功能号和子功能号，则返回记录的数据；
+/// + 若该功能号不使用子功能号（ECX），则忽略子功能号，返回子功能号为 0 的数据；
+/// + 否则返回全 0，与 AMD 处理器查询不支持的功能号时的行为一致。
+#[derive(Clone)]
+pub struct CpuidTable {
+    entries: [CpuidEntry; CpuidTable::CAPACITY],
+    len: usize,
+}
+
+impl CpuidTable {
+    pub const CAPACITY: usize = 256;
+
+    pub fn new() -> Self {
+        Self {
+            entries: [CpuidEntry::default(); Self::CAPACITY],
+            len: 0,
+        }
+    }
+
+    /// 使用子功能号（ECX）的功能号，其余功能号在执行 cpuid 指令时会忽略 ECX。
+    pub(crate) fn has_sub_leaf(leaf: u32) -> bool {
+        matches!(
+            leaf,
+            0x04 | 0x07
+                | 0x0b
+                | 0x0d
+                | 0x0f
+                | 0x10
+                | 0x12
+                | 0x14
+                | 0x17
+                | 0x18
+                | 0x1b
+                | 0x1d
+                | 0x1f
+                | 0x20
+                | 0x23
+                | 0x24
+                | 0x8000_001d
+                | 0x8000_0020
+                | 0x8000_0026
+        )
+    }
+
+    /// 插入一条数据，若已存在相同的功能号和子功能号，则覆盖原有数据。
+    pub fn insert(
+        &mut self,
+        leaf: u32,
+        sub_leaf: u32,
+        result: CpuidResult,
+    ) -> Result<(), CpuidTableError> {
+        if let Some(entry) = self.entries[..self.len]
+            .iter_mut()
+            .find(|e| e.leaf == leaf && e.sub_leaf == sub_leaf)
+        {
+            entry.result = result;
+            return Ok(());
+        }
+        if self.len >= Self::CAPACITY {
+            return Err(CpuidTableError::CapacityExceeded);
+        }
+        self.entries[self.len] = CpuidEntry {
+            leaf,
+            sub_leaf,
+            result,
+        };
+        self.len += 1;
+        Ok(())
+    }
+
+    /// 完全匹配功能号和子功能号的数据
+    pub fn get(&self, leaf: u32, sub_leaf: u32) -> Option<&CpuidResult> {
+        self.entries()
+            .iter()
+            .find(|e| e.leaf == leaf && e.sub_leaf == sub_leaf)
+            .map(|e| &e.result)
+    }
+
+    /// 按插入顺序排列的所有数据，调用 [`sort`](Self::sort) 后按功能号、子功能号升序排列。
+    pub fn entries(&self) -> &[CpuidEntry] {
+        &self.entries[..self.len]
+    }
+
+    /// 将所有数据按功能号、子功能号升序排列
+    pub fn sort(&mut self) {
+        self.entries[..self.len].sort_unstable_by_key(|entry| (entry.leaf, entry.sub_leaf));
+    }
+
+    /// 解析 `cpuid -r` 输出的原始格式，例如：
+    ///
+    /// ```text
+    /// CPU 0:
+    ///    0x00000000 0x00: eax=0x00000016 ebx=0x756e6547 ecx=0x6c65746e edx=0x49656e69
+    ///    0x00000001 0x00: eax=0x000906ea ebx=0x00100800 ecx=0x7ffafbff edx=0xbfebfbff
+    /// ```
+    ///
+    /// 只读取第一个处理器的数据，空行会被忽略。
+    pub fn parse(text: &str) -> Result<Self, CpuidTableError> {
+        let mut table = Self::new();
+        let mut cpu_count = 0;
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with("CPU") {
+                cpu_count += 1;
+                if cpu_count > 1 {
+                    break;
+                }
+                continue;
+            }
+            let syntax_error = CpuidTableError::Syntax { line: index + 1 };
+            let (leaf, sub_leaf, result) = Self::parse_line(line).ok_or(syntax_error)?;
+            table.insert(leaf, sub_leaf, result)?;
+        }
+        Ok(table)
+    }
+
+    fn parse_line(line: &str) -> Option<(u32, u32, CpuidResult)> {
+        fn hex(text: &str) -> Option<u32> {
+            let text = text
+                .strip_prefix("0x")
+                .or_else(|| text.strip_prefix("0X"))?;
+            u32::from_str_radix(text, 16).ok()
+        }
+        let mut tokens = line.split_whitespace();
+        let leaf = hex(tokens.next()?)?;
+        let sub_leaf = hex(tokens.next()?.strip_suffix(':')?)?;
+        let mut result = CpuidResult::default();
+        for _ in 0..4 {
+            let mut kv = tokens.next()?.splitn(2, '=');
+            let (key, value) = (kv.next()?, hex(kv.next()?)?);
+            match key {
+                "eax" => result.eax = value,
+                "ebx" => result.ebx = value,
+                "ecx" => result.ecx = value,
+                "edx" => result.edx = value,
+                _ => return None,
+            }
+        }
+        if tokens.next().is_some() {
+            return None;
+        }
+        Some((leaf, sub_leaf, result))
+    }
+}
+
+impl Default for CpuidTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CpuidSource for CpuidTable {
+    fn query(&self, leaf: usize, sub_leaf: usize) -> CpuidResult {
+        let leaf = leaf as u32;
+        let sub_leaf = if Self::has_sub_leaf(leaf) {
+            sub_leaf as u32
+        } else {
+            0
+        };
+        self.get(leaf, sub_leaf).copied().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cpuid::{
+        cache::CacheType, topology::ApicIdParts, vendor::Vendor, CpuidResult, CpuidSource,
+    };
+
+    use super::{CpuidTable, CpuidTableError};
+
+    const COFFEE_LAKE: &str = include_str!("testdata/coffee_lake.txt");
+
+    #[test]
+    fn parse_dump() {
+        let table = CpuidTable::parse(COFFEE_LAKE).unwrap();
+        // 只读取第一个处理器
+        assert_eq!(table.entries().len(), 43);
+        assert_eq!(table.get(0x0b, 1).map(|r| r.ebx), Some(0x0c));
+        // 01h 功能号不使用子功能号
+        assert_eq!(table.query(0x01, 3).eax, 0x0009_06ea);
+        assert_eq!(table.query(0x04, 7).eax, 0);
+        assert_eq!(table.query(0x4000_0000, 0).eax, 0);
+
+        assert_eq!(
+            CpuidTable::parse("CPU 0:\n   0x00000000 0x00: eax=0x1 ebx=0x2 ecx=0x3\n").err(),
+            Some(CpuidTableError::Syntax { line: 2 })
+        );
+    }
+
+    #[test]
+    fn decode_from_table() {
+        let table = CpuidTable::parse(COFFEE_LAKE).unwrap();
+        assert_eq!(table.vendor(), Vendor::Intel);
+        assert_eq!(
+            table.brand_string().unwrap().as_str(),
+            "Intel(R) Core(TM) i7-8700 CPU @ 3.20GHz"
+        );
+        let signature = table.signature();
+        assert_eq!((signature.family(), signature.model()), (0x06, 0x9e));
+
+        let std_feature = table.std_feature();
+        assert!(std_feature.support_msr() && std_feature.support_pcid());
+        let structured = table.structured_ext_feature();
+        assert!(structured.support_smep() && structured.support_smap());
+        assert!(!structured.support_avx512f());
+        let ext_feature = table.ext_feature();
+        assert!(ext_feature.support_long_mode() && ext_feature.support_page1gb());
+
+        let mut caches = table.caches();
+        let l1d = caches.next().unwrap();
+        assert_eq!((l1d.level(), l1d.cache_type()), (1, Some(CacheType::Data)));
+        assert_eq!(l1d.size(), 32 * 1024);
+        let l3 = caches.nth(2).unwrap();
+        assert_eq!(l3.size(), 12 * 1024 * 1024);
+        assert!(caches.next().is_none());
+
+        assert_eq!(
+            table.topology().split(0x0b),
+            ApicIdParts {
+                package: 0,
+                die: 0,
+                core: 5,
+                thread: 1
+            }
+        );
+        let widths = table.address_widths();
+        assert_eq!((widths.physical(), widths.linear()), (39, 48));
+        let xsave = table.xsave_info().unwrap();
+        assert_eq!(xsave.standard_size(xsave.supported_xcr0()), 0x440);
+    }
+
+    #[test]
+    fn insert_replaces() {
+        let mut table = CpuidTable::new();
+        let result = CpuidResult {
+            eax: 1,
+            ..Default::default()
+        };
+        table.insert(0x00, 0, result).unwrap();
+        table.insert(0x00, 0, CpuidResult::default()).unwrap();
+        assert_eq!(table.entries().len(), 1);
+        assert_eq!(table.query(0x00, 0).eax, 0);
+    }
+}
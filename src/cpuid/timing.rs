@@ -0,0 +1,301 @@
+use super::{hypervisor::Hypervisor, vendor::Vendor, CpuidSource};
+
+/// TSC 频率的来源，按可信程度从高到低排列。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TscFrequencySource {
+    /// 虚拟机监视器通过 `CPUID.(EAX=4000_0010h):EAX` 直接给出的频率（kHz）
+    Hypervisor,
+    /// `CPUID.(EAX=15h)` 同时给出了 TSC/晶振比例和晶振频率
+    CrystalRatio,
+    /// `CPUID.(EAX=15h)` 给出了 TSC/晶振比例，晶振频率根据处理器型号确定
+    KnownCrystal,
+    /// `CPUID.(EAX=16h):EAX` 中的处理器基准频率（MHz），精度较低
+    BaseFrequency,
+}
+
+/// 由 CPUID 得出的 TSC 频率
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TscFrequency {
+    pub hz: u64,
+    pub source: TscFrequencySource,
+}
+
+/// # TSC 信息
+///
+/// 按以下顺序确定 TSC 频率，均无法确定时需要通过其他时钟源校准：
+///
+/// 1. 运行在虚拟机监视器之上时，使用 4000_0010h 功能号（VMware 定义，KVM、ACRN 等也会提供），
+///    虚拟机中的 15h 功能号通常直接透传宿主机的数值，无法反映 TSC 缩放；
+/// 2. `CPUID.(EAX=15h)`：`TSC 频率 = 晶振频率 × EBX / EAX`，
+///    ECX 为 0 时，部分 Intel 处理器的晶振频率可以根据型号确定；
+/// 3. `CPUID.(EAX=16h):EAX` 中的处理器基准频率。
+///
+/// 只有 [`invariant`](Self::invariant) 为 true 时，TSC 才会在 P/C/T 状态切换时保持恒定速率，
+/// 否则由 CPUID 得出的频率仅在初始状态下可用。
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TscInfo {
+    invariant: bool,
+    /// (EBX, EAX)，即 (分子, 分母)
+    ratio: Option<(u32, u32)>,
+    crystal_hz: Option<u64>,
+    base_mhz: Option<u16>,
+    max_mhz: Option<u16>,
+    bus_mhz: Option<u16>,
+    hypervisor_tsc_khz: Option<u32>,
+    hypervisor_bus_khz: Option<u32>,
+    frequency: Option<TscFrequency>,
+}
+
+impl TscInfo {
+    pub(crate) fn from_cpuid<S: CpuidSource + ?Sized>(cpuid: &S) -> Self {
+        let mut info = Self::default();
+
+        // 8000_0007h: EDX[8] 不变 TSC
+        if let Ok(result) = cpuid.query_checked(0x8000_0007, 0) {
+            info.invariant = result.edx & (1 << 8) != 0;
+        }
+
+        if let Ok(result) = cpuid.query_checked(0x15, 0) {
+            if result.eax != 0 && result.ebx != 0 {
+                info.ratio = Some((result.ebx, result.eax));
+            }
+            if result.ecx != 0 {
+                info.crystal_hz = Some(result.ecx as u64);
+            }
+        }
+
+        if let Ok(result) = cpuid.query_checked(0x16, 0) {
+            let mhz = |reg: u32| Some(reg as u16).filter(|&mhz| mhz != 0);
+            info.base_mhz = mhz(result.eax);
+            info.max_mhz = mhz(result.ebx);
+            info.bus_mhz = mhz(result.ecx);
+        }
+
+        if let Some(hypervisor) = Hypervisor::detect(cpuid) {
+            if let Some(result) = hypervisor.query(cpuid, 0x10) {
+                info.hypervisor_tsc_khz = Some(result.eax).filter(|&khz| khz != 0);
+                info.hypervisor_bus_khz = Some(result.ebx).filter(|&khz| khz != 0);
+            }
+        }
+
+        info.frequency = info.derive_frequency(cpuid);
+        info
+    }
+
+    fn derive_frequency<S: CpuidSource + ?Sized>(&self, cpuid: &S) -> Option<TscFrequency> {
+        if let Some(khz) = self.hypervisor_tsc_khz {
+            return Some(TscFrequency {
+                hz: khz as u64 * 1000,
+                source: TscFrequencySource::Hypervisor,
+            });
+        }
+        if let Some((numerator, denominator)) = self.ratio {
+            let crystal = match self.crystal_hz {
+                Some(hz) => Some((hz, TscFrequencySource::CrystalRatio)),
+                None => known_crystal_hz(cpuid).map(|hz| (hz, TscFrequencySource::KnownCrystal)),
+            };
+            if let Some((crystal_hz, source)) = crystal {
+                return Some(TscFrequency {
+                    hz: crystal_hz * numerator as u64 / denominator as u64,
+                    source,
+                });
+            }
+        }
+        self.base_mhz.map(|mhz| TscFrequency {
+            hz: mhz as u64 * 1_000_000,
+            source: TscFrequencySource::BaseFrequency,
+        })
+    }
+
+    /// TSC 是否以恒定速率运行，即 `CPUID.(EAX=8000_0007h):EDX[8]`
+    pub fn invariant(&self) -> bool {
+        self.invariant
+    }
+    /// TSC 频率及其来源
+    pub fn frequency(&self) -> Option<TscFrequency> {
+        self.frequency
+    }
+    /// TSC 频率（Hz）
+    pub fn frequency_hz(&self) -> Option<u64> {
+        self.frequency.map(|frequency| frequency.hz)
+    }
+    /// TSC 与晶振频率之比，即 `CPUID.(EAX=15h)` 的 (EBX, EAX)
+    pub fn ratio(&self) -> Option<(u32, u32)> {
+        self.ratio
+    }
+    /// `CPUID.(EAX=15h):ECX` 中枚举的晶振频率（Hz）
+    pub fn crystal_hz(&self) -> Option<u64> {
+        self.crystal_hz
+    }
+    /// 处理器基准频率（MHz）
+    pub fn base_mhz(&self) -> Option<u16> {
+        self.base_mhz
+    }
+    /// 处理器最大频率（MHz）
+    pub fn max_mhz(&self) -> Option<u16> {
+        self.max_mhz
+    }
+    /// 总线（参考）频率（MHz）
+    pub fn bus_mhz(&self) -> Option<u16> {
+        self.bus_mhz
+    }
+    /// 虚拟机监视器提供的 local APIC 定时器频率（kHz）
+    pub fn hypervisor_bus_khz(&self) -> Option<u32> {
+        self.hypervisor_bus_khz
+    }
+}
+
+/// `CPUID.(EAX=15h):ECX` 为 0 的 Intel 处理器的晶振频率。
+fn known_crystal_hz<S: CpuidSource + ?Sized>(cpuid: &S) -> Option<u64> {
+    if cpuid.vendor() != Vendor::Intel {
+        return None;
+    }
+    let signature = cpuid.signature();
+    if signature.family() != 6 {
+        return None;
+    }
+    match signature.model() {
+        // Skylake、Kaby Lake、Coffee Lake
+        0x4e | 0x5e | 0x8e | 0x9e => Some(24_000_000),
+        // Goldmont（Denverton）
+        0x5f => Some(25_000_000),
+        // Goldmont（Apollo Lake）
+        0x5c => Some(19_200_000),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cpuid::{table::CpuidTable, CpuidResult, CpuidSource};
+
+    use super::TscFrequencySource;
+
+    #[test]
+    fn known_crystal() {
+        let table = CpuidTable::parse(include_str!("testdata/coffee_lake.txt")).unwrap();
+        let info = table.tsc_info();
+        assert!(info.invariant());
+        assert_eq!(info.ratio(), Some((0x10a, 2)));
+        assert_eq!(info.crystal_hz(), None);
+        assert_eq!(info.base_mhz(), Some(3200));
+        assert_eq!(info.max_mhz(), Some(4800));
+        assert_eq!(info.bus_mhz(), Some(100));
+        let frequency = info.frequency().unwrap();
+        assert_eq!(frequency.hz, 3_192_000_000);
+        assert_eq!(frequency.source, TscFrequencySource::KnownCrystal);
+    }
+
+    #[test]
+    fn crystal_and_base_frequency() {
+        let mut table = CpuidTable::new();
+        table
+            .insert(
+                0x00,
+                0,
+                CpuidResult {
+                    eax: 0x16,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        table
+            .insert(
+                0x16,
+                0,
+                CpuidResult {
+                    eax: 2000,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        // 未知晶振频率时使用基准频率
+        table
+            .insert(
+                0x15,
+                0,
+                CpuidResult {
+                    eax: 2,
+                    ebx: 200,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let frequency = table.tsc_info().frequency().unwrap();
+        assert_eq!(frequency.hz, 2_000_000_000);
+        assert_eq!(frequency.source, TscFrequencySource::BaseFrequency);
+
+        table
+            .insert(
+                0x15,
+                0,
+                CpuidResult {
+                    eax: 2,
+                    ebx: 200,
+                    ecx: 38_400_000,
+                    edx: 0,
+                },
+            )
+            .unwrap();
+        let info = table.tsc_info();
+        assert!(!info.invariant());
+        assert_eq!(info.frequency_hz(), Some(3_840_000_000));
+        assert_eq!(
+            info.frequency().unwrap().source,
+            TscFrequencySource::CrystalRatio
+        );
+    }
+
+    #[test]
+    fn hypervisor_frequency() {
+        let mut table = CpuidTable::new();
+        table
+            .insert(
+                0x00,
+                0,
+                CpuidResult {
+                    eax: 0x01,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        table
+            .insert(
+                0x01,
+                0,
+                CpuidResult {
+                    ecx: 1 << 31,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        table
+            .insert(
+                0x4000_0000,
+                0,
+                CpuidResult {
+                    eax: 0x4000_0010,
+                    ebx: 0x4b4d_564b,
+                    ecx: 0x564b_4d56,
+                    edx: 0x4d,
+                },
+            )
+            .unwrap();
+        table
+            .insert(
+                0x4000_0010,
+                0,
+                CpuidResult {
+                    eax: 2_899_998,
+                    ebx: 1_000_000,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let info = table.tsc_info();
+        let frequency = info.frequency().unwrap();
+        assert_eq!(frequency.hz, 2_899_998_000);
+        assert_eq!(frequency.source, TscFrequencySource::Hypervisor);
+        assert_eq!(info.hypervisor_bus_khz(), Some(1_000_000));
+    }
+}
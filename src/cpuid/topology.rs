@@ -0,0 +1,381 @@
+use super::{CpuidResult, CpuidSource};
+
+/// 0Bh 和 1Fh 功能号中的层级类型，即 `ECX[15:8]`。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TopologyLevel {
+    Smt = 1,
+    Core = 2,
+    Module = 3,
+    Tile = 4,
+    Die = 5,
+    DieGroup = 6,
+}
+
+impl TopologyLevel {
+    fn from_raw(data: u8) -> Option<Self> {
+        match data {
+            1 => Some(TopologyLevel::Smt),
+            2 => Some(TopologyLevel::Core),
+            3 => Some(TopologyLevel::Module),
+            4 => Some(TopologyLevel::Tile),
+            5 => Some(TopologyLevel::Die),
+            6 => Some(TopologyLevel::DieGroup),
+            _ => None,
+        }
+    }
+}
+
+/// APIC ID 按拓扑层级拆分后的结果。
+///
+/// 除 package 之外，其余 ID 均是相对于上一层级的 ID，例如 core 为 die 内的 core 编号。
+/// Module、Tile 等中间层级合并在 core 和 die 之间，可通过 [`Topology::level_id`] 单独获取。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ApicIdParts {
+    pub package: u32,
+    pub die: u32,
+    pub core: u32,
+    pub thread: u32,
+}
+
+/// # 处理器拓扑
+///
+/// 记录每个层级在 APIC ID 中的偏移，即：将 APIC ID 右移该偏移即可得到上一层级的 ID。
+///
+/// 支持以下几种枚举方式，按优先级排列：
+///
+/// 1. AMD `CPUID.(EAX=8000_0008h):ECX` 和 `CPUID.(EAX=8000_001Eh)`；
+/// 2. `CPUID.(EAX=1Fh)`，V2 扩展拓扑枚举；
+/// 3. `CPUID.(EAX=0Bh)`，扩展拓扑枚举；
+/// 4. `CPUID.(EAX=01h):EBX[23:16]` 和 `CPUID.(EAX=04h):EAX[31:26]`，仅适用于 8 位的 xAPIC ID。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Topology {
+    shifts: [Option<u8>; 6],
+    package_shift: u8,
+}
+
+/// 至少需要多少 bit 才能表示 `0..count`
+fn ceil_log2(count: u32) -> u8 {
+    if count <= 1 {
+        0
+    } else {
+        (32 - (count - 1).leading_zeros()) as u8
+    }
+}
+
+fn mask(width: u8) -> u32 {
+    if width >= 32 {
+        u32::MAX
+    } else {
+        (1 << width) - 1
+    }
+}
+
+impl Topology {
+    /// 由 0Bh 或 1Fh 功能号的所有子功能号构建，遇到层级类型为 0 的子功能号即结束。
+    ///
+    /// 若第一个子功能号的 EBX 为 0，则表示不支持该功能号，返回 None。
+    pub(crate) fn from_extended_leaf<I: IntoIterator<Item = CpuidResult>>(
+        sub_leaves: I,
+    ) -> Option<Self> {
+        let mut topology = Topology {
+            shifts: [None; 6],
+            package_shift: 0,
+        };
+        let mut valid = false;
+        for result in sub_leaves {
+            let level_type = ((result.ecx >> 8) & 0xff) as u8;
+            if level_type == 0 || (!valid && result.ebx & 0xffff == 0) {
+                break;
+            }
+            valid = true;
+            let shift = (result.eax & 0x1f) as u8;
+            if let Some(level) = TopologyLevel::from_raw(level_type) {
+                topology.shifts[level as usize - 1] = Some(shift);
+            }
+            topology.package_shift = topology.package_shift.max(shift);
+        }
+        if valid {
+            Some(topology)
+        } else {
+            None
+        }
+    }
+
+    /// 由 AMD 扩展功能号构建：
+    ///
+    /// + `leaf_8000_0008.ecx`：`[15:12]` 为 ApicIdCoreIdSize，`[7:0]` 为 package 内逻辑处理器数量减 1；
+    /// + `leaf_8000_001e`：`EBX[15:8]` 为每个 core 的线程数减 1，`ECX[10:8]` 为每个 package 的 node（die）数量减 1。
+    ///   不支持 TopologyExtensions 时传入 None。
+    pub(crate) fn from_amd(
+        leaf_8000_0008: &CpuidResult,
+        leaf_8000_001e: Option<&CpuidResult>,
+    ) -> Self {
+        let ecx = leaf_8000_0008.ecx;
+        let mut package_shift = ((ecx >> 12) & 0xf) as u8;
+        if package_shift == 0 {
+            // 旧式处理器未提供 ApicIdCoreIdSize，根据逻辑处理器数量计算
+            package_shift = ceil_log2((ecx & 0xff) + 1);
+        }
+        let (threads, nodes) = match leaf_8000_001e {
+            Some(result) => (
+                ((result.ebx >> 8) & 0xff) + 1,
+                ((result.ecx >> 8) & 0x7) + 1,
+            ),
+            None => (1, 1),
+        };
+        let smt_shift = ceil_log2(threads).min(package_shift);
+        let die_bits = ceil_log2(nodes);
+        let core_shift = package_shift.saturating_sub(die_bits).max(smt_shift);
+        let mut shifts = [None; 6];
+        shifts[TopologyLevel::Smt as usize - 1] = Some(smt_shift);
+        shifts[TopologyLevel::Core as usize - 1] = Some(core_shift);
+        if die_bits != 0 {
+            shifts[TopologyLevel::Die as usize - 1] = Some(package_shift);
+        }
+        Topology {
+            shifts,
+            package_shift,
+        }
+    }
+
+    /// 由 01h 功能号的 `EBX[23:16]`（package 内逻辑处理器的最大数量）
+    /// 和 04h 功能号的 `EAX[31:26]`（package 内 core 的最大数量减 1）构建。
+    pub(crate) fn from_legacy(logical_count: u32, core_count: u32) -> Self {
+        let package_shift = ceil_log2(logical_count);
+        let threads_per_core = if core_count == 0 {
+            1
+        } else {
+            (logical_count / core_count).max(1)
+        };
+        let smt_shift = ceil_log2(threads_per_core).min(package_shift);
+        let mut shifts = [None; 6];
+        shifts[TopologyLevel::Smt as usize - 1] = Some(smt_shift);
+        shifts[TopologyLevel::Core as usize - 1] = Some(package_shift);
+        Topology {
+            shifts,
+            package_shift,
+        }
+    }
+
+    /// 该层级的偏移，即该层级及其以下所有层级在 APIC ID 中占据的 bit 数；未枚举的层级返回 None。
+    pub fn shift(&self, level: TopologyLevel) -> Option<u8> {
+        self.shifts[level as usize - 1]
+    }
+
+    /// 将 APIC ID 右移该偏移，即可得到 package ID。
+    pub fn package_shift(&self) -> u8 {
+        self.package_shift
+    }
+
+    /// 某一层级的起始偏移，即其下一层级的偏移。
+    fn base_shift(&self, level: TopologyLevel) -> u8 {
+        self.shifts[..level as usize - 1]
+            .iter()
+            .filter_map(|shift| *shift)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// 获取 APIC ID 在某一层级内的 ID，该层级未枚举时返回 None。
+    pub fn level_id(&self, apic_id: u32, level: TopologyLevel) -> Option<u32> {
+        let shift = self.shift(level)?;
+        let base = self.base_shift(level);
+        if base >= 32 {
+            return Some(0);
+        }
+        Some((apic_id >> base) & mask(shift.saturating_sub(base)))
+    }
+
+    /// 将 APIC ID 拆分为 package、die、core 和 thread。
+    pub fn split(&self, apic_id: u32) -> ApicIdParts {
+        ApicIdParts {
+            package: if self.package_shift >= 32 {
+                0
+            } else {
+                apic_id >> self.package_shift
+            },
+            die: self.level_id(apic_id, TopologyLevel::Die).unwrap_or(0),
+            core: self.level_id(apic_id, TopologyLevel::Core).unwrap_or(0),
+            thread: self.level_id(apic_id, TopologyLevel::Smt).unwrap_or(0),
+        }
+    }
+}
+
+/// 依次读取 0Bh 或 1Fh 功能号的子功能号
+struct ExtendedLeafIter<'a, S: CpuidSource + ?Sized> {
+    cpuid: &'a S,
+    leaf: u32,
+    sub_leaf: u32,
+}
+
+impl<'a, S: CpuidSource + ?Sized> Iterator for ExtendedLeafIter<'a, S> {
+    type Item = CpuidResult;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // 目前最多定义了 6 个层级
+        if self.sub_leaf > 8 {
+            return None;
+        }
+        // 层级类型为 0 的子功能号无效
+        let result = self.cpuid.query_checked(self.leaf, self.sub_leaf).ok()?;
+        self.sub_leaf += 1;
+        Some(result)
+    }
+}
+
+impl Topology {
+    pub(crate) fn from_cpuid<S: CpuidSource + ?Sized>(cpuid: &S) -> Self {
+        if cpuid.vendor().is_amd_compatible() {
+            if let Ok(leaf_8000_0008) = cpuid.query_checked(0x8000_0008, 0) {
+                let leaf_8000_001e = if cpuid.ext_feature().support_topology_ext() {
+                    cpuid.query_checked(0x8000_001e, 0).ok()
+                } else {
+                    None
+                };
+                return Topology::from_amd(&leaf_8000_0008, leaf_8000_001e.as_ref());
+            }
+        }
+        for &leaf in [0x1f, 0x0b].iter() {
+            let sub_leaves = ExtendedLeafIter {
+                cpuid,
+                leaf,
+                sub_leaf: 0,
+            };
+            if let Some(topology) = Topology::from_extended_leaf(sub_leaves) {
+                return topology;
+            }
+        }
+        let logical_count = cpuid.std_feature().max_logical_processors() as u32;
+        let core_count = match cpuid.query_checked(0x04, 0) {
+            Ok(result) => (result.eax >> 26) + 1,
+            Err(_) => 1,
+        };
+        Topology::from_legacy(logical_count, core_count)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::println;
+
+    use crate::cpuid::{Cpuid, CpuidResult, CpuidSource};
+
+    use super::{ApicIdParts, Topology, TopologyLevel};
+
+    fn sub_leaf(shift: u32, level_type: u32, logical_count: u32) -> CpuidResult {
+        CpuidResult {
+            eax: shift,
+            ebx: logical_count,
+            ecx: level_type << 8,
+            edx: 0,
+        }
+    }
+
+    #[test]
+    fn topology_test() {
+        if let Some(cpuid) = Cpuid::inst() {
+            let topology = cpuid.topology();
+            println!("{:?} {:?}", topology, topology.split(cpuid.apic_id()));
+        }
+    }
+
+    #[test]
+    fn leaf_0b() {
+        let topology = Topology::from_extended_leaf(
+            [sub_leaf(1, 1, 2), sub_leaf(4, 2, 16), sub_leaf(0, 0, 0)]
+                .iter()
+                .copied(),
+        )
+        .unwrap();
+        assert_eq!(topology.shift(TopologyLevel::Smt), Some(1));
+        assert_eq!(topology.shift(TopologyLevel::Core), Some(4));
+        assert_eq!(topology.package_shift(), 4);
+        assert_eq!(
+            topology.split(0x1b),
+            ApicIdParts {
+                package: 1,
+                die: 0,
+                core: 5,
+                thread: 1
+            }
+        );
+    }
+
+    #[test]
+    fn leaf_1f_with_die() {
+        let topology = Topology::from_extended_leaf(
+            [
+                sub_leaf(1, 1, 2),
+                sub_leaf(3, 2, 8),
+                sub_leaf(4, 3, 16),
+                sub_leaf(6, 5, 64),
+                sub_leaf(0, 0, 0),
+            ]
+            .iter()
+            .copied(),
+        )
+        .unwrap();
+        // package 2，die 3，module 1，core 2，thread 1
+        let apic_id = (2 << 6) | (3 << 4) | (1 << 3) | (2 << 1) | 1;
+        assert_eq!(
+            topology.split(apic_id),
+            ApicIdParts {
+                package: 2,
+                die: 3,
+                core: 2,
+                thread: 1
+            }
+        );
+        assert_eq!(topology.level_id(apic_id, TopologyLevel::Module), Some(1));
+        assert_eq!(topology.level_id(apic_id, TopologyLevel::Tile), None);
+    }
+
+    #[test]
+    fn unsupported_extended_leaf() {
+        assert!(Topology::from_extended_leaf([sub_leaf(0, 1, 0)].iter().copied()).is_none());
+    }
+
+    #[test]
+    fn amd() {
+        // 2 个 node，每个 node 8 个 core，每个 core 2 个线程
+        let leaf_8000_0008 = CpuidResult {
+            eax: 0,
+            ebx: 0,
+            ecx: (5 << 12) | 31,
+            edx: 0,
+        };
+        let leaf_8000_001e = CpuidResult {
+            eax: 0,
+            ebx: 1 << 8,
+            ecx: 1 << 8,
+            edx: 0,
+        };
+        let topology = Topology::from_amd(&leaf_8000_0008, Some(&leaf_8000_001e));
+        assert_eq!(topology.shift(TopologyLevel::Smt), Some(1));
+        assert_eq!(topology.shift(TopologyLevel::Core), Some(4));
+        assert_eq!(topology.shift(TopologyLevel::Die), Some(5));
+        assert_eq!(
+            topology.split((1 << 5) | (1 << 4) | (7 << 1)),
+            ApicIdParts {
+                package: 1,
+                die: 1,
+                core: 7,
+                thread: 0
+            }
+        );
+    }
+
+    #[test]
+    fn legacy() {
+        let topology = Topology::from_legacy(8, 4);
+        assert_eq!(
+            topology.split(0x0d),
+            ApicIdParts {
+                package: 1,
+                die: 0,
+                core: 2,
+                thread: 1
+            }
+        );
+    }
+}
@@ -0,0 +1,96 @@
+use core::fmt::Display;
+
+/// # 处理器制造商
+///
+/// 由 `CPUID.(EAX=0h)` 返回的 12 字节制造商字符串（按 EBX、EDX、ECX 顺序拼接）决定。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Vendor {
+    /// `GenuineIntel`
+    Intel,
+    /// `AuthenticAMD`
+    Amd,
+    /// `HygonGenuine`，海光，其 MSR 及扩展功能号与 AMD 基本一致。
+    Hygon,
+    /// `  Shanghai  `，兆芯
+    Zhaoxin,
+    /// `CentaurHauls`，VIA 以及早期的兆芯处理器
+    Centaur,
+    /// 无法识别的制造商字符串，常见于模拟器或者虚拟机监视器修改过的 CPUID。
+    Unknown([u8; 12]),
+}
+
+impl Vendor {
+    pub fn from_bytes(bytes: [u8; 12]) -> Self {
+        match &bytes {
+            b"GenuineIntel" => Vendor::Intel,
+            b"AuthenticAMD" => Vendor::Amd,
+            b"HygonGenuine" => Vendor::Hygon,
+            b"  Shanghai  " => Vendor::Zhaoxin,
+            b"CentaurHauls" => Vendor::Centaur,
+            _ => Vendor::Unknown(bytes),
+        }
+    }
+    pub(crate) fn from_regs(ebx: u32, edx: u32, ecx: u32) -> Self {
+        let mut bytes = [0u8; 12];
+        bytes[0..4].copy_from_slice(&ebx.to_le_bytes());
+        bytes[4..8].copy_from_slice(&edx.to_le_bytes());
+        bytes[8..12].copy_from_slice(&ecx.to_le_bytes());
+        Self::from_bytes(bytes)
+    }
+    /// AMD 和海光共享同一套扩展功能号和 MSR 定义。
+    pub fn is_amd_compatible(&self) -> bool {
+        matches!(self, Vendor::Amd | Vendor::Hygon)
+    }
+}
+
+impl Display for Vendor {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Vendor::Intel => f.write_str("GenuineIntel"),
+            Vendor::Amd => f.write_str("AuthenticAMD"),
+            Vendor::Hygon => f.write_str("HygonGenuine"),
+            Vendor::Zhaoxin => f.write_str("  Shanghai  "),
+            Vendor::Centaur => f.write_str("CentaurHauls"),
+            Vendor::Unknown(bytes) => {
+                f.write_str(core::str::from_utf8(bytes).unwrap_or("unknown vendor"))
+            }
+        }
+    }
+}
+
+/// # 处理器商标字符串
+///
+/// 由 `CPUID.(EAX=8000_0002h..=8000_0004h)` 的 EAX、EBX、ECX、EDX 依次拼接而成，一共 48 字节，
+/// 以 NUL 结尾，部分处理器会在字符串前填充空格。
+pub struct BrandString {
+    pub(crate) bytes: [u8; 48],
+}
+
+impl BrandString {
+    pub fn as_bytes(&self) -> &[u8; 48] {
+        &self.bytes
+    }
+    /// 去除首部空格和尾部 NUL 后的字符串，若不是合法的 UTF-8 编码，则返回空字符串。
+    pub fn as_str(&self) -> &str {
+        let end = self
+            .bytes
+            .iter()
+            .position(|&b| b == 0)
+            .unwrap_or(self.bytes.len());
+        core::str::from_utf8(&self.bytes[..end])
+            .unwrap_or("")
+            .trim()
+    }
+}
+
+impl Display for BrandString {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl core::fmt::Debug for BrandString {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "BrandString({:?})", self.as_str())
+    }
+}
@@ -0,0 +1,248 @@
+use super::{CpuidResult, CpuidSource};
+
+/// XCR0 和 IA32_XSS 中各个状态组件对应的 bit。
+pub mod component {
+    pub const X87: u64 = 1 << 0;
+    pub const SSE: u64 = 1 << 1;
+    pub const AVX: u64 = 1 << 2;
+    pub const BNDREGS: u64 = 1 << 3;
+    pub const BNDCSR: u64 = 1 << 4;
+    pub const OPMASK: u64 = 1 << 5;
+    pub const ZMM_HI256: u64 = 1 << 6;
+    pub const HI16_ZMM: u64 = 1 << 7;
+    /// 仅 IA32_XSS
+    pub const PT: u64 = 1 << 8;
+    pub const PKRU: u64 = 1 << 9;
+    /// 仅 IA32_XSS
+    pub const PASID: u64 = 1 << 10;
+    /// 仅 IA32_XSS
+    pub const CET_U: u64 = 1 << 11;
+    /// 仅 IA32_XSS
+    pub const CET_S: u64 = 1 << 12;
+    /// 仅 IA32_XSS
+    pub const HDC: u64 = 1 << 13;
+    /// 仅 IA32_XSS
+    pub const UINTR: u64 = 1 << 14;
+    /// 仅 IA32_XSS
+    pub const LBR: u64 = 1 << 15;
+    /// 仅 IA32_XSS
+    pub const HWP: u64 = 1 << 16;
+    pub const XTILECFG: u64 = 1 << 17;
+    pub const XTILEDATA: u64 = 1 << 18;
+}
+
+/// # XSAVE 状态组件
+///
+/// 由 `CPUID.(EAX=0Dh, ECX=i)`（i ≥ 2）解码而来，x87 和 SSE 两个组件固定位于 legacy 区域。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct XsaveComponent {
+    /// 组件大小（字节）
+    pub size: u32,
+    /// 标准格式下，组件相对于 XSAVE 区域起始处的偏移；supervisor 组件始终为 0。
+    pub offset: u32,
+    /// 是否为 IA32_XSS 管理的 supervisor 组件
+    pub supervisor: bool,
+    /// 压缩格式下，组件是否需要 64 字节对齐
+    pub align64: bool,
+}
+
+/// # XSAVE 信息
+///
+/// 由 `CPUID.(EAX=0Dh)` 的所有子功能号构建：
+///
+/// + 子功能号 0：XCR0 所支持的组件，以及标准格式的区域大小；
+/// + 子功能号 1：XSAVEOPT/XSAVEC/XGETBV/XSAVES 的支持情况，以及 IA32_XSS 所支持的组件；
+/// + 子功能号 i（2 ≤ i ≤ 63）：第 i 个组件的大小、偏移以及对齐要求。
+#[derive(Debug, Clone)]
+pub struct XsaveInfo {
+    xcr0_supported: u64,
+    xss_supported: u64,
+    enabled_size: u32,
+    max_size: u32,
+    flags: u32,
+    components: [XsaveComponent; 64],
+}
+
+impl XsaveInfo {
+    /// legacy 区域（512 字节）和 XSAVE header（64 字节）的总大小
+    pub const LEGACY_AND_HEADER_SIZE: u32 = 576;
+
+    /// `query` 的参数为 0Dh 功能号的子功能号。
+    pub(crate) fn from_query<F: Fn(u32) -> CpuidResult>(query: F) -> Self {
+        let sub0 = query(0);
+        let sub1 = query(1);
+        let xcr0_supported = (sub0.eax as u64) | ((sub0.edx as u64) << 32);
+        let xss_supported = (sub1.ecx as u64) | ((sub1.edx as u64) << 32);
+        let mut components = [XsaveComponent::default(); 64];
+        components[0] = XsaveComponent {
+            size: 160,
+            offset: 0,
+            supervisor: false,
+            align64: false,
+        };
+        components[1] = XsaveComponent {
+            size: 256,
+            offset: 160,
+            supervisor: false,
+            align64: false,
+        };
+        let supported = xcr0_supported | xss_supported;
+        for i in 2..64 {
+            if supported & (1 << i) == 0 {
+                continue;
+            }
+            let result = query(i);
+            components[i as usize] = XsaveComponent {
+                size: result.eax,
+                offset: result.ebx,
+                supervisor: result.ecx & 0b01 != 0,
+                align64: result.ecx & 0b10 != 0,
+            };
+        }
+        Self {
+            xcr0_supported,
+            xss_supported,
+            enabled_size: sub0.ebx,
+            max_size: sub0.ecx,
+            flags: sub1.eax,
+            components,
+        }
+    }
+
+    pub(crate) fn from_cpuid<S: CpuidSource + ?Sized>(cpuid: &S) -> Self {
+        Self::from_query(|sub_leaf| cpuid.query_checked(0x0d, sub_leaf).unwrap_or_default())
+    }
+
+    /// XCR0 所支持的组件
+    pub fn supported_xcr0(&self) -> u64 {
+        self.xcr0_supported
+    }
+    /// IA32_XSS 所支持的组件
+    pub fn supported_xss(&self) -> u64 {
+        self.xss_supported
+    }
+    /// 当前 XCR0 所使能的组件在标准格式下所需的区域大小
+    pub fn enabled_size(&self) -> u32 {
+        self.enabled_size
+    }
+    /// XCR0 所支持的全部组件在标准格式下所需的区域大小
+    pub fn max_size(&self) -> u32 {
+        self.max_size
+    }
+    pub fn support_xsaveopt(&self) -> bool {
+        self.flags & (1 << 0) != 0
+    }
+    pub fn support_xsavec(&self) -> bool {
+        self.flags & (1 << 1) != 0
+    }
+    /// 支持 `XGETBV` 指令的 ECX = 1
+    pub fn support_xgetbv1(&self) -> bool {
+        self.flags & (1 << 2) != 0
+    }
+    /// 支持 XSAVES/XRSTORS 指令以及 IA32_XSS 寄存器
+    pub fn support_xsaves(&self) -> bool {
+        self.flags & (1 << 3) != 0
+    }
+    /// 不支持的组件返回 None
+    pub fn component(&self, index: u32) -> Option<&XsaveComponent> {
+        if index >= 64 || (self.xcr0_supported | self.xss_supported) & (1 << index) == 0 {
+            return None;
+        }
+        Some(&self.components[index as usize])
+    }
+
+    /// 标准格式（XSAVE/XSAVEOPT）下保存 `mask` 中的组件所需的区域大小。
+    ///
+    /// 标准格式不支持 supervisor 组件，`mask` 中不支持的组件和 supervisor 组件均会被忽略。
+    pub fn standard_size(&self, mask: u64) -> u32 {
+        let mask = mask & self.xcr0_supported;
+        (2..64)
+            .filter(|i| mask & (1 << i) != 0)
+            .map(|i| &self.components[i])
+            .filter(|c| !c.supervisor)
+            .map(|c| c.offset + c.size)
+            .fold(Self::LEGACY_AND_HEADER_SIZE, u32::max)
+    }
+
+    /// 压缩格式（XSAVEC/XSAVES）下保存 `mask` 中的组件所需的区域大小。
+    ///
+    /// 组件按编号从小到大依次紧密排列，需要 64 字节对齐的组件会先将偏移向上对齐。
+    /// `mask` 中不支持的组件会被忽略。
+    pub fn compacted_size(&self, mask: u64) -> u32 {
+        let mask = mask & (self.xcr0_supported | self.xss_supported);
+        (2..64)
+            .filter(|i| mask & (1 << i) != 0)
+            .map(|i| &self.components[i])
+            .fold(Self::LEGACY_AND_HEADER_SIZE, |offset, c| {
+                let offset = if c.align64 {
+                    (offset + 63) & !63
+                } else {
+                    offset
+                };
+                offset + c.size
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::println;
+
+    use crate::cpuid::{Cpuid, CpuidResult, CpuidSource};
+
+    use super::{component, XsaveInfo};
+
+    #[test]
+    fn xsave_test() {
+        if let Some(cpuid) = Cpuid::inst() {
+            if let Some(xsave) = cpuid.xsave_info() {
+                println!(
+                    "{:#x} {:#x} {} {}",
+                    xsave.supported_xcr0(),
+                    xsave.supported_xss(),
+                    xsave.standard_size(xsave.supported_xcr0()),
+                    xsave.compacted_size(xsave.supported_xcr0() | xsave.supported_xss())
+                );
+            }
+        }
+    }
+
+    /// 取自支持 AVX-512 和 CET 的处理器
+    fn query(sub_leaf: u32) -> CpuidResult {
+        let (eax, ebx, ecx, edx) = match sub_leaf {
+            0 => (0x0000_02e7, 0x0000_0a88, 0x0000_0a88, 0),
+            1 => (0x0000_000f, 0x0000_0998, 0x0000_1900, 0),
+            2 => (0x100, 0x240, 0, 0),
+            5 => (0x40, 0x440, 0, 0),
+            6 => (0x200, 0x480, 0, 0),
+            7 => (0x400, 0x680, 0, 0),
+            8 => (0x48, 0, 1, 0),
+            9 => (0x8, 0xa80, 0, 0),
+            11 => (0x10, 0, 1, 0),
+            12 => (0x18, 0, 1, 0),
+            _ => (0, 0, 0, 0),
+        };
+        CpuidResult { eax, ebx, ecx, edx }
+    }
+
+    #[test]
+    fn save_area_size() {
+        let xsave = XsaveInfo::from_query(query);
+        assert_eq!(xsave.supported_xcr0(), 0x2e7);
+        assert_eq!(xsave.supported_xss(), 0x1900);
+        assert!(xsave.support_xsaves());
+        assert_eq!(xsave.component(3), None);
+        assert_eq!(xsave.component(8).map(|c| c.supervisor), Some(true));
+
+        let mask = component::X87 | component::SSE | component::AVX;
+        assert_eq!(xsave.standard_size(mask), 0x340);
+        assert_eq!(xsave.compacted_size(mask), 0x340);
+        assert_eq!(xsave.standard_size(xsave.supported_xcr0()), 0xa88);
+        // supervisor 组件不会出现在标准格式中
+        assert_eq!(xsave.standard_size(mask | component::CET_U), 0x340);
+        assert_eq!(
+            xsave.compacted_size(mask | component::PKRU | component::CET_U),
+            0x340 + 0x8 + 0x10
+        );
+    }
+}
@@ -29,11 +29,23 @@ impl Cr0 {
         unsafe {
             asm!("mov {}, cr0", out(reg) x.data);
         }
-        Clean::from_raw(x)
+        Clean { raw_buffer: x }
+    }
+    /// 与 [`take`](Cr0::take) 相同，但缓冲区已被占用时返回 None，而不是 panic。
+    #[inline]
+    pub fn buffer(&self) -> Option<Clean<Cr0Buffer>> {
+        let mut raw_buffer = unsafe { CR0BUFFER_INSTANCE.take()? };
+        unsafe {
+            asm!("mov {}, cr0", out(reg) raw_buffer.data);
+        }
+        Some(Clean { raw_buffer })
     }
     pub(crate) fn inst_once() -> Self {
         unsafe { CR0_INST.take().unwrap() }
     }
+    pub(crate) unsafe fn inst_uncheck() -> Option<Self> {
+        CR0_INST.take()
+    }
 }
 
 pub struct Cr0Buffer {
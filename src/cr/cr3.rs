@@ -7,7 +7,7 @@ use core::marker::PhantomData;
 
 use register::RegisterBufferFlush;
 
-use crate::Clean;
+use crate::{cpuid::address::AddressWidths, ArchError, Clean, Dirty};
 
 pub struct Cr3 {
     phatom: PhantomData<usize>,
@@ -74,6 +74,28 @@ impl Drop for Cr3Buffer {
     }
 }
 
+/// 检查最高级别页转换表的物理地址：需要 4KB 对齐，并且不能超出 MAXPHYADDR。
+pub(crate) fn check_table_base(addr: usize, widths: &AddressWidths) -> Result<usize, ArchError> {
+    if addr & 0xfff != 0 {
+        return Err(ArchError::AddressNotAligned);
+    }
+    if !widths.is_valid_physical(addr as u64) {
+        return Err(ArchError::AddressExceedsPhysicalWidth);
+    }
+    Ok(addr >> 12)
+}
+
+impl Dirty<Cr3Buffer> {
+    /// 写入最高级别页转换表的物理地址，地址需要 4KB 对齐，并且不能超出 MAXPHYADDR，
+    /// 否则分别返回错误 `ArchError::AddressNotAligned` 和 `ArchError::AddressExceedsPhysicalWidth`。
+    ///
+    /// 超出 MAXPHYADDR 的 bit 属于保留位，刷新到 CR3 寄存器时会导致 #GP 异常。
+    pub fn set_table_base(self, addr: usize, widths: &AddressWidths) -> Result<Self, ArchError> {
+        let tba = check_table_base(addr, widths)?;
+        Ok(self.write::<fields::TBA>(tba))
+    }
+}
+
 #[cfg(target_arch = "x86_64")]
 impl Clean<Cr3Buffer> {
     /// 1. 判断处理器是否支持 pcid，
@@ -1,8 +1,8 @@
 use register::{RegisterBufferFlush, RegisterBufferReader, RegisterBufferWriter};
 
-use crate::Clean;
+use crate::{cpuid::address::AddressWidths, ArchError, Clean};
 
-use super::{fields, Cr3Buffer};
+use super::{check_table_base, fields, Cr3Buffer};
 
 pub struct Cr3BufferPcid {
     pub(super) buffer: Cr3Buffer,
@@ -21,6 +21,16 @@ impl crate::Dirty<Cr3BufferPcid> {
         };
         self
     }
+    /// 参见 `Dirty<Cr3Buffer>::set_table_base`
+    pub fn set_table_base(
+        mut self,
+        addr: usize,
+        widths: &AddressWidths,
+    ) -> Result<Self, ArchError> {
+        let tba = check_table_base(addr, widths)?;
+        self.raw_buffer.buffer.write::<fields::TBA>(tba);
+        Ok(self)
+    }
 }
 
 impl Clean<Cr3BufferPcid> {
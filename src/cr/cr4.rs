@@ -3,8 +3,9 @@ use core::marker::PhantomData;
 use register::RegisterBufferFlush;
 
 use crate::{
+    cpuid::feature::StructuredExtFeature,
     mem::segment::{cs::Cs, selector::Privilege},
-    Clean, Dirty,
+    ArchError, Clean, Dirty,
 };
 
 /// Cr4 寄存器除 PCE 位之外，其余 bit 使能前均可以使用 CPUID 指令来判断是否支持该特性。
@@ -87,6 +88,57 @@ impl Dirty<Cr4Buffer> {
     pub unsafe fn disable_pcid_uncheck(self) -> Self {
         self.write::<fields::PCIDE>(false)
     }
+    /// 使能 SMEP，需要 `CPUID.(EAX=07h,ECX=0):EBX[7] = 1`，
+    /// 否则返回错误 `ArchError::FeatureIsNotSupported`。
+    pub fn enable_smep(self, feature: &StructuredExtFeature) -> Result<Self, ArchError> {
+        if !feature.support_smep() {
+            return Err(ArchError::FeatureIsNotSupported);
+        }
+        Ok(self.write::<fields::SMEP>(true))
+    }
+    /// 使能 SMAP，需要 `CPUID.(EAX=07h,ECX=0):EBX[20] = 1`，
+    /// 否则返回错误 `ArchError::FeatureIsNotSupported`。
+    pub fn enable_smap(self, feature: &StructuredExtFeature) -> Result<Self, ArchError> {
+        if !feature.support_smap() {
+            return Err(ArchError::FeatureIsNotSupported);
+        }
+        Ok(self.write::<fields::SMAP>(true))
+    }
+    /// 使能 RDFSBASE 等指令，需要 `CPUID.(EAX=07h,ECX=0):EBX[0] = 1`，
+    /// 否则返回错误 `ArchError::FeatureIsNotSupported`。
+    pub fn enable_fsgsbase(self, feature: &StructuredExtFeature) -> Result<Self, ArchError> {
+        if !feature.support_fsgsbase() {
+            return Err(ArchError::FeatureIsNotSupported);
+        }
+        Ok(self.write::<fields::FSGSBASE>(true))
+    }
+    /// 使能 UMIP，需要 `CPUID.(EAX=07h,ECX=0):ECX[2] = 1`，
+    /// 否则返回错误 `ArchError::FeatureIsNotSupported`。
+    pub fn enable_umip(self, feature: &StructuredExtFeature) -> Result<Self, ArchError> {
+        if !feature.support_umip() {
+            return Err(ArchError::FeatureIsNotSupported);
+        }
+        Ok(self.write::<fields::UMIP>(true))
+    }
+    /// 使能用户态页保护键，需要 `CPUID.(EAX=07h,ECX=0):ECX[3] = 1`，
+    /// 否则返回错误 `ArchError::FeatureIsNotSupported`。
+    pub fn enable_pke(self, feature: &StructuredExtFeature) -> Result<Self, ArchError> {
+        if !feature.support_pku() {
+            return Err(ArchError::FeatureIsNotSupported);
+        }
+        Ok(self.write::<fields::PKE>(true))
+    }
+    /// 使能 CET，需要支持影子栈 `CPUID.(EAX=07h,ECX=0):ECX[7]`
+    /// 或间接分支跟踪 `CPUID.(EAX=07h,ECX=0):EDX[20]` 中的任意一个，
+    /// 否则返回错误 `ArchError::FeatureIsNotSupported`。
+    ///
+    /// ❗ `CR0.WP = 0` 时置 CET 会导致 #GP 异常。
+    pub fn enable_cet(self, feature: &StructuredExtFeature) -> Result<Self, ArchError> {
+        if !feature.support_cet_ss() && !feature.support_cet_ibt() {
+            return Err(ArchError::FeatureIsNotSupported);
+        }
+        Ok(self.write::<fields::CET>(true))
+    }
 }
 
 impl_reg_buffer_trait!(Cr4Buffer);
@@ -101,6 +153,11 @@ pub mod fields {
             pub OSXSAVE     [18, rw, bool],
             pub(crate) PCIDE[17, rw, bool],
             pub FSGSBASE    [16, rw, bool],
+            /// # 5 级分页
+            /// 57-bit linear addresses
+            ///
+            /// 只能在 long 模式未激活时修改，long 模式下修改会导致 #GP 异常。
+            pub LA57        [12, rw, bool],
             pub UMIP        [11, rw, bool],
             pub OSXMMEXCPT  [10, rw, bool],
             pub OSFXSR      [09, rw, bool],
@@ -2,7 +2,7 @@ pub struct Flags;
 
 #[derive(PartialEq, Eq, Clone, Copy)]
 pub struct FlagsBuffer {
-    data: usize,
+    pub(crate) data: usize,
 }
 
 impl Flags {
@@ -80,6 +80,10 @@ impl Flags {
     }
 }
 impl FlagsBuffer {
+    /// 所有标志位均为 0 的缓冲区，用于构建标志位掩码（例如 SFMASK），而不是写回 rFlags。
+    pub const fn empty() -> Self {
+        Self { data: 0 }
+    }
     /// 1. 你无法通过本函数修改 VIP、VIF、VM 标签。
     /// 2. ⚠️  virtual-8086 模式下，如果 IOPL 字段小于 3，且 VME 没有使能，使用本函数则会导致 #GP 异常。
     ///
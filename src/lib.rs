@@ -9,9 +9,10 @@ use register::{RegisterBufferFlush, RegisterBufferReader, RegisterBufferWriter};
 extern crate std;
 
 macro_rules! plain_field {
-    ($Struct:path {$($Vis:vis $Fn:ident:$Field:path),* $(,)?}) => {
+    ($Struct:path {$($(#[$Attr:meta])* $Vis:vis $Fn:ident:$Field:path),* $(,)?}) => {
         impl $Struct {
             $(
+                $(#[$Attr])*
                 #[inline]
                 $Vis fn $Fn(&self)-><$Field as bits::field::Field<$Struct>>::ValueType {
                     use register::RegisterBufferReader;
@@ -67,6 +68,7 @@ macro_rules! def_const {
         )+
     };
 }
+pub mod apic;
 pub mod arch;
 pub mod cpuid;
 pub mod cr;
@@ -78,6 +80,28 @@ pub enum ArchError {
     LongModeInactivated,
     PcidIsNotSupported,
     PcidDisabled,
+    /// 处理器不支持所要使能的特性
+    FeatureIsNotSupported,
+    /// 地址没有按要求对齐
+    AddressNotAligned,
+    /// 物理地址超出了处理器所支持的宽度（MAXPHYADDR）
+    AddressExceedsPhysicalWidth,
+    /// 线性地址不是规范地址
+    AddressNotCanonical,
+    /// 选择器或其引用的段描述符不满足要求
+    InvalidSegmentLayout,
+    /// 寄存器编号超出了处理器所支持的数量
+    IndexOutOfRange,
+    /// 内存类型不能用于该寄存器
+    InvalidMemType,
+    /// 与已有的范围重叠，并且内存类型的组合没有定义
+    MemTypeConflict,
+    /// 不允许的模式转换
+    InvalidModeTransition,
+    /// 目标超出了当前模式所支持的范围
+    InvalidDestination,
+    /// 必需的配置项没有设置
+    MissingConfiguration,
 }
 
 pub struct Clean<T: RegisterBufferReader + RegisterBufferWriter + RegisterBufferFlush> {
@@ -8,6 +8,19 @@ pub struct Descriptor {
 
 impl_buffer_trait!(Descriptor);
 
+impl Descriptor {
+    /// 由描述符的 8 字节原始值构建，低 32 bit 在前。
+    pub const fn from_raw(raw: u64) -> Self {
+        Self {
+            low: raw as u32,
+            high: (raw >> 32) as u32,
+        }
+    }
+    pub const fn raw(&self) -> u64 {
+        (self.low as u64) | ((self.high as u64) << 32)
+    }
+}
+
 pub enum TssDescriptor {
     Available16bit,
     Available32bit,
@@ -1,6 +1,52 @@
+mod update;
+pub use update::{CacheControl, CrCacheControl, MtrrUpdate};
+
+use bits::field::{BufferReader, BufferWriter};
+
+use crate::{
+    cpuid::{address::AddressWidths, feature::StdFeature},
+    msr::MsrAccess,
+    ArchError,
+};
+
+pub struct MtrrCap;
+impl MtrrCap {
+    pub const REG_ADDR: u32 = 0x00fe;
+}
+
+pub struct MtrrDefaultType;
+impl MtrrDefaultType {
+    pub const REG_ADDR: u32 = 0x02ff;
+}
+
+/// 第 n 个可变范围的基址寄存器和掩码寄存器的地址分别为 `200h + 2n` 和 `201h + 2n`。
+pub struct MtrrPhys;
+impl MtrrPhys {
+    pub const fn base_reg_addr(index: u8) -> u32 {
+        0x0200 + 2 * index as u32
+    }
+    pub const fn mask_reg_addr(index: u8) -> u32 {
+        0x0201 + 2 * index as u32
+    }
+}
+
+/// 00000h-7FFFFh，8 个 64KB 的范围
+pub struct MtrrFix64K;
+impl MtrrFix64K {
+    pub const REG_ADDR: u32 = 0x0250;
+}
+
+/// 80000h-BFFFFh，16 个 16KB 的范围
+pub struct MtrrFix16K;
+impl MtrrFix16K {
+    pub const LOW_REG_ADDR: u32 = 0x0258;
+    pub const TOP_REG_ADDR: u32 = 0x0259;
+}
+
+/// C0000h-FFFFFh，64 个 4KB 的范围
 pub struct MtrrFix4K;
 impl MtrrFix4K {
-    pub const LOW_REG_ADDR: u32 = 0x0267;
+    pub const LOW_REG_ADDR: u32 = 0x0268;
     pub const TOP_REG_ADDR: u32 = 0x026f;
 }
 
@@ -17,6 +63,92 @@ pub struct MtrrPhysMaskBuffer {
 pub struct MtrrDefaultTypeBuffer {
     data: u64,
 }
+impl_buffer_trait! {
+    MtrrCapBuffer;
+    MtrrPhysBaseBuffer;
+    MtrrPhysMaskBuffer;
+    MtrrDefaultTypeBuffer;
+}
+
+impl MtrrCapBuffer {
+    /// 可变范围 MTRR 的数量
+    pub fn variable_count(&self) -> u8 {
+        self.read::<fields::VCNT>()
+    }
+    pub fn support_fixed(&self) -> bool {
+        self.read::<fields::FIX>()
+    }
+    pub fn support_wc(&self) -> bool {
+        self.read::<fields::WC>()
+    }
+}
+
+impl MtrrDefaultTypeBuffer {
+    /// 未被任何 MTRR 覆盖的物理地址的内存类型
+    pub fn mem_type(&self) -> MemType {
+        self.read::<fields::Type>()
+    }
+    pub fn enabled(&self) -> bool {
+        self.read::<fields::E>()
+    }
+    pub fn fixed_enabled(&self) -> bool {
+        self.read::<fields::FE>()
+    }
+}
+
+impl MtrrPhysBaseBuffer {
+    pub fn mem_type(&self) -> MemType {
+        self.read::<fields::Type>()
+    }
+    /// 可变范围的物理基地址
+    pub fn phys_base(&self) -> u64 {
+        self.read::<fields::PhysBase>() << 12
+    }
+    /// 写入可变范围的物理基地址，地址需要 4KB 对齐，并且不能超出 MAXPHYADDR，
+    /// 否则分别返回错误 `ArchError::AddressNotAligned` 和 `ArchError::AddressExceedsPhysicalWidth`。
+    pub fn set_phys_base(
+        &mut self,
+        addr: u64,
+        widths: &AddressWidths,
+    ) -> Result<&mut Self, ArchError> {
+        if addr & 0xfff != 0 {
+            return Err(ArchError::AddressNotAligned);
+        }
+        if !widths.is_valid_physical(addr) {
+            return Err(ArchError::AddressExceedsPhysicalWidth);
+        }
+        Ok(self.write::<fields::PhysBase>(addr >> 12))
+    }
+}
+
+impl MtrrPhysMaskBuffer {
+    /// 可变范围的物理地址掩码（低 12 bit 为 0）
+    pub fn phys_mask(&self) -> u64 {
+        self.read::<fields::PhysMask>() << 12
+    }
+    /// 写入可变范围的物理地址掩码，低 12 bit 需要为 0，并且不能超出 MAXPHYADDR，
+    /// 否则分别返回错误 `ArchError::AddressNotAligned` 和 `ArchError::AddressExceedsPhysicalWidth`。
+    ///
+    /// 超出 MAXPHYADDR 的 bit 属于保留位，写入后会导致 #GP 异常。
+    pub fn set_phys_mask(
+        &mut self,
+        mask: u64,
+        widths: &AddressWidths,
+    ) -> Result<&mut Self, ArchError> {
+        if mask & 0xfff != 0 {
+            return Err(ArchError::AddressNotAligned);
+        }
+        if !widths.is_valid_physical(mask) {
+            return Err(ArchError::AddressExceedsPhysicalWidth);
+        }
+        Ok(self.write::<fields::PhysMask>(mask >> 12))
+    }
+    pub fn valid(&self) -> bool {
+        self.read::<fields::V>()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct MemType {
     pub(crate) data: u8,
 }
@@ -58,9 +190,348 @@ def_const! {
         /// 2. 👌写时若 cache 缺失，则申请缓存行，并且会申请到一个处于修改状态下的缓存行。
         ///
         /// 修改状态下的缓存行会被回写到内存中。
-        WB:0x06
+        WB:0x06,
+        /// ### Uncached
+        /// 弱不可缓存
+        ///
+        /// 仅用于 PAT，在 MTRR 中为保留值。与 UC 相同，但可以被 MTRR 的 WC 类型覆盖。
+        UC_MINUS:0x07
     }
 }
+
+impl MemType {
+    /// MTRR 只支持 UC、WC、WT、WP、WB，其余的值均为保留值。
+    pub fn is_valid_for_mtrr(&self) -> bool {
+        matches!(self.data, 0x00 | 0x01 | 0x04 | 0x05 | 0x06)
+    }
+
+    /// 两个重叠的可变范围的实际内存类型，组合未定义时返回 None。
+    fn overlap(self, other: MemType) -> Option<MemType> {
+        let is_wt_or_wb = |mem_type: MemType| mem_type == MemType::WT || mem_type == MemType::WB;
+        if self == other {
+            Some(self)
+        } else if self == MemType::UC || other == MemType::UC {
+            Some(MemType::UC)
+        } else if is_wt_or_wb(self) && is_wt_or_wb(other) {
+            Some(MemType::WT)
+        } else {
+            None
+        }
+    }
+}
+/// # 可变范围
+///
+/// 由一对 IA32_MTRR_PHYSBASEn、IA32_MTRR_PHYSMASKn 解码而来。
+/// 物理地址 `addr` 满足 `addr & mask == base & mask` 时落于该范围内。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VariableRange {
+    pub index: u8,
+    pub base: u64,
+    pub mask: u64,
+    /// 范围的大小，掩码不连续时该值没有意义
+    pub size: u64,
+    pub mem_type: MemType,
+}
+
+impl VariableRange {
+    pub fn contains(&self, addr: u64) -> bool {
+        addr & self.mask == self.base & self.mask
+    }
+}
+
+/// # 固定范围
+///
+/// 11 个固定范围寄存器将物理地址的前 1MB 划分为 88 个子范围，
+/// 每个寄存器的 8 个字节依次为 8 个子范围的内存类型。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedRanges {
+    types: [MemType; 88],
+}
+
+impl FixedRanges {
+    /// 固定范围所覆盖的物理地址上限（1MB）
+    pub const LIMIT: u64 = 0x10_0000;
+
+    /// 依次为 64K、16K 以及 4K 寄存器的值，共 11 个。
+    pub fn from_raw(regs: &[u64; 11]) -> Self {
+        let mut types = [MemType::UC; 88];
+        for (i, mem_type) in types.iter_mut().enumerate() {
+            mem_type.data = (regs[i / 8] >> (i % 8 * 8)) as u8;
+        }
+        Self { types }
+    }
+
+    /// [`from_raw`](Self::from_raw) 的逆运算
+    pub fn raw(&self) -> [u64; 11] {
+        let mut regs = [0; 11];
+        for (i, mem_type) in self.types.iter().enumerate() {
+            regs[i / 8] |= (mem_type.data as u64) << (i % 8 * 8);
+        }
+        regs
+    }
+
+    /// 将 `[start, start + len)` 所覆盖的子范围设置为 `mem_type`，超出 1MB 的部分会被忽略。
+    pub fn set(&mut self, start: u64, len: u64, mem_type: MemType) -> &mut Self {
+        let end = start.saturating_add(len);
+        for (index, slot) in self.types.iter_mut().enumerate() {
+            let (range_start, size) = Self::range(index);
+            if range_start < end && range_start + size > start {
+                *slot = mem_type;
+            }
+        }
+        self
+    }
+
+    /// 第 `index` 个子范围的起始地址和大小
+    fn range(index: usize) -> (u64, u64) {
+        match index {
+            0..=7 => (index as u64 * 0x1_0000, 0x1_0000),
+            8..=23 => (0x8_0000 + (index as u64 - 8) * 0x4000, 0x4000),
+            _ => (0xc_0000 + (index as u64 - 24) * 0x1000, 0x1000),
+        }
+    }
+
+    fn index_of(addr: u64) -> Option<usize> {
+        match addr {
+            0..=0x7_ffff => Some((addr >> 16) as usize),
+            0x8_0000..=0xb_ffff => Some(8 + ((addr - 0x8_0000) >> 14) as usize),
+            0xc_0000..=0xf_ffff => Some(24 + ((addr - 0xc_0000) >> 12) as usize),
+            _ => None,
+        }
+    }
+
+    /// 超出 1MB 的地址返回 None
+    pub fn mem_type(&self, addr: u64) -> Option<MemType> {
+        Self::index_of(addr).map(|index| self.types[index])
+    }
+
+    /// 依次返回每个子范围的 (起始地址, 大小, 内存类型)
+    pub fn iter(&self) -> impl Iterator<Item = (u64, u64, MemType)> + '_ {
+        self.types.iter().enumerate().map(|(index, &mem_type)| {
+            let (start, size) = Self::range(index);
+            (start, size, mem_type)
+        })
+    }
+}
+
+/// # MTRR 驱动
+///
+/// 通过 [`MsrAccess`] 读取 MTRR，测试时可以替换为伪造的寄存器。
+pub struct Mtrr<A: MsrAccess> {
+    access: A,
+    cap: MtrrCapBuffer,
+}
+
+impl<A: MsrAccess> Mtrr<A> {
+    /// 需要 `CPUID.01H:EDX.MTRR[12] = 1`，否则返回 None。
+    pub fn new(access: A, std_feature: &StdFeature) -> Option<Self> {
+        if !std_feature.support_mtrr() {
+            return None;
+        }
+        let cap = MtrrCapBuffer {
+            data: access.read_msr(MtrrCap::REG_ADDR),
+        };
+        Some(Self { access, cap })
+    }
+
+    pub fn cap(&self) -> &MtrrCapBuffer {
+        &self.cap
+    }
+
+    pub fn access(&mut self) -> &mut A {
+        &mut self.access
+    }
+
+    pub fn into_inner(self) -> A {
+        self.access
+    }
+
+    pub fn default_type(&self) -> MtrrDefaultTypeBuffer {
+        MtrrDefaultTypeBuffer {
+            data: self.access.read_msr(MtrrDefaultType::REG_ADDR),
+        }
+    }
+
+    /// 第 `index` 个可变范围，超出 VCNT 或者 V 位为 0 时返回 None。
+    pub fn variable_range(&self, index: u8, widths: &AddressWidths) -> Option<VariableRange> {
+        if index >= self.cap.variable_count() {
+            return None;
+        }
+        let base = MtrrPhysBaseBuffer {
+            data: self.access.read_msr(MtrrPhys::base_reg_addr(index)),
+        };
+        let mask = MtrrPhysMaskBuffer {
+            data: self.access.read_msr(MtrrPhys::mask_reg_addr(index)),
+        };
+        if !mask.valid() {
+            return None;
+        }
+        let physical_mask = widths.physical_mask() & !0xfff;
+        let phys_mask = mask.phys_mask() & physical_mask;
+        Some(VariableRange {
+            index,
+            base: base.phys_base() & physical_mask,
+            mask: phys_mask,
+            size: (!phys_mask & widths.physical_mask()).wrapping_add(1),
+            mem_type: base.mem_type(),
+        })
+    }
+
+    /// 遍历所有有效的可变范围
+    pub fn variable_ranges<'a>(
+        &'a self,
+        widths: &'a AddressWidths,
+    ) -> impl Iterator<Item = VariableRange> + 'a {
+        (0..self.cap.variable_count()).filter_map(move |index| self.variable_range(index, widths))
+    }
+
+    /// 处理器不支持固定范围 MTRR 时返回 None
+    pub fn fixed_ranges(&self) -> Option<FixedRanges> {
+        if !self.cap.support_fixed() {
+            return None;
+        }
+        let mut regs = [0; 11];
+        regs[0] = self.access.read_msr(MtrrFix64K::REG_ADDR);
+        regs[1] = self.access.read_msr(MtrrFix16K::LOW_REG_ADDR);
+        regs[2] = self.access.read_msr(MtrrFix16K::TOP_REG_ADDR);
+        for (reg, addr) in regs[3..]
+            .iter_mut()
+            .zip(MtrrFix4K::LOW_REG_ADDR..=MtrrFix4K::TOP_REG_ADDR)
+        {
+            *reg = self.access.read_msr(addr);
+        }
+        Some(FixedRanges::from_raw(&regs))
+    }
+
+    /// 读取所有 MTRR，可变范围超出 [`MtrrState::MAX_VARIABLE_RANGES`] 的部分会被忽略。
+    pub fn state(&self, widths: &AddressWidths) -> MtrrState {
+        let default_type = self.default_type();
+        let mut state = MtrrState::new(default_type.mem_type());
+        state.enabled = default_type.enabled();
+        if default_type.fixed_enabled() {
+            state.fixed = self.fixed_ranges();
+        }
+        for range in self.variable_ranges(widths) {
+            if state.push_variable(range).is_none() {
+                break;
+            }
+        }
+        state
+    }
+}
+
+/// # MTRR 状态
+///
+/// MTRR 配置的快照，用于计算任意物理地址范围的实际内存类型，不访问任何寄存器。
+#[derive(Debug, Clone)]
+pub struct MtrrState {
+    /// IA32_MTRR_DEF_TYPE.E，为 false 时所有物理地址均为 UC
+    pub enabled: bool,
+    pub default_type: MemType,
+    /// 仅当固定范围 MTRR 被支持并且使能（FE = 1）时为 Some
+    pub fixed: Option<FixedRanges>,
+    variable: [Option<VariableRange>; Self::MAX_VARIABLE_RANGES],
+}
+
+impl MtrrState {
+    pub const MAX_VARIABLE_RANGES: usize = 32;
+
+    /// 已使能、不含任何范围的状态
+    pub fn new(default_type: MemType) -> Self {
+        Self {
+            enabled: true,
+            default_type,
+            fixed: None,
+            variable: [None; Self::MAX_VARIABLE_RANGES],
+        }
+    }
+
+    /// 超出 [`Self::MAX_VARIABLE_RANGES`] 时返回 None
+    pub fn push_variable(&mut self, range: VariableRange) -> Option<()> {
+        let slot = self.variable.iter_mut().find(|slot| slot.is_none())?;
+        *slot = Some(range);
+        Some(())
+    }
+
+    /// 替换编号相同的可变范围，超出 [`Self::MAX_VARIABLE_RANGES`] 时返回 None
+    pub fn set_variable(&mut self, range: VariableRange) -> Option<()> {
+        match self
+            .variable
+            .iter_mut()
+            .flatten()
+            .find(|slot| slot.index == range.index)
+        {
+            Some(slot) => {
+                *slot = range;
+                Some(())
+            }
+            None => self.push_variable(range),
+        }
+    }
+
+    pub fn remove_variable(&mut self, index: u8) -> Option<VariableRange> {
+        self.variable
+            .iter_mut()
+            .find(|slot| matches!(slot, Some(range) if range.index == index))?
+            .take()
+    }
+
+    pub fn variable_ranges(&self) -> impl Iterator<Item = &VariableRange> {
+        self.variable.iter().flatten()
+    }
+
+    /// 单个物理地址的内存类型，多个可变范围重叠且类型冲突时，行为未定义，返回 None。
+    ///
+    /// 1. MTRR 未使能时为 UC；
+    /// 2. 1MB 以下的地址由固定范围决定；
+    /// 3. 不在任何可变范围内时为默认类型；
+    /// 4. 重叠的可变范围中：类型相同时为该类型，含有 UC 时为 UC，只含有 WT 和 WB 时为 WT。
+    pub fn mem_type(&self, addr: u64) -> Option<MemType> {
+        if !self.enabled {
+            return Some(MemType::UC);
+        }
+        if let Some(mem_type) = self.fixed.as_ref().and_then(|fixed| fixed.mem_type(addr)) {
+            return Some(mem_type);
+        }
+        let mut matched = self
+            .variable_ranges()
+            .filter(|range| range.contains(addr))
+            .map(|range| range.mem_type);
+        let first = match matched.next() {
+            Some(mem_type) => mem_type,
+            None => return Some(self.default_type),
+        };
+        matched.try_fold(first, MemType::overlap)
+    }
+
+    /// 物理地址范围 `[start, start + len)` 的内存类型。
+    ///
+    /// 范围内的内存类型不一致、或者存在未定义的重叠时返回 None。
+    /// 范围的边界按照可变范围的基址和大小计算，因此不支持掩码不连续的可变范围。
+    pub fn effective_type(&self, start: u64, len: u64) -> Option<MemType> {
+        let end = start.saturating_add(len.max(1));
+        let first = self.mem_type(start)?;
+        let in_range = |addr: u64| addr > start && addr < end;
+        let fixed_bounds = self
+            .fixed
+            .iter()
+            .flat_map(|fixed| fixed.iter())
+            .map(|(range_start, _, _)| range_start)
+            .chain(core::iter::once(FixedRanges::LIMIT));
+        let variable_bounds = self
+            .variable_ranges()
+            .flat_map(|range| [range.base, range.base.wrapping_add(range.size)]);
+        fixed_bounds
+            .chain(variable_bounds)
+            .filter(|&addr| in_range(addr))
+            .try_for_each(|addr| match self.mem_type(addr) {
+                Some(mem_type) if mem_type == first => Some(()),
+                _ => None,
+            })?;
+        Some(first)
+    }
+}
+
 pub mod fields {
     use super::{
         MemType, MtrrCapBuffer, MtrrDefaultTypeBuffer, MtrrPhysBaseBuffer, MtrrPhysMaskBuffer,
@@ -84,15 +555,15 @@ pub mod fields {
     bits::fields_ex! {
         MtrrPhysBaseBuffer [data] {
             /// 52bit 物理空间的基地址，至少 4KB 对齐，不保存低 12bit（永远为 0）
-            PhysBase    [12..=51, rw, u64],
+            pub(super) PhysBase    [12..=51, rw, u64],
         }
         MtrrPhysMaskBuffer [data] {
             /// ### 物理地址范围掩码
             ///
             /// 同时和物理基地址、目的物理地址做与运算，如果两个值相等，则目标物理地址落于物理地址范围内。
             /// 和网络掩码类似的道理。
-            PhysMask    [12..=51, rw, u64],
-            V           [11, rw, bool]
+            pub(super) PhysMask    [12..=51, rw, u64],
+            pub V       [11, rw, bool]
         }
         MtrrDefaultTypeBuffer [data] {
             /// ### MTRR Enable
@@ -103,19 +574,154 @@ pub mod fields {
             /// + 清 0 时，所有的固定范围、可变范围的 MTRR 均被禁用，并且内存类型被置为默认的 UC(uncacheable).
             ///
             /// 该 bit 为不影响 RdMem 和 WrMem 字段的操作。
-            E   [11, rw, bool],
+            pub E   [11, rw, bool],
             /// ### Fixed-Range Enable
             ///
             /// + 当 FE 置 1 时，所有固定范围的 MTRR 均被启用。
             /// + 当 FE 清 0 时，所有固定范围的 MTRR 均被禁用。
             ///
             /// 该 bit 位对可变范围 MTRR 没有影响。
-            FE  [10, rw, bool]
+            pub FE  [10, rw, bool]
         }
         MtrrCapBuffer [data] {
-            WC      [10, ro, bool],
-            FIX     [08, ro, bool],
-            VCNT    [0..=7, ro, bool]
+            pub WC      [10, ro, bool],
+            pub FIX     [08, ro, bool],
+            /// 可变范围 MTRR 的数量
+            pub VCNT    [0..=7, ro, u8]
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        cpuid::{address::AddressWidths, feature::StdFeature},
+        msr::FakeMsr,
+    };
+
+    use super::{FixedRanges, MemType, Mtrr, MtrrState};
+
+    pub(super) fn fake_mtrr() -> FakeMsr {
+        let regs = [
+            (0x0fe, 0x0508),
+            (0x2ff, 0x0c00),
+            // 0-9FFFFh WB，A0000h-BFFFFh UC，C0000h-FFFFFh WP
+            (0x250, 0x0606_0606_0606_0606),
+            (0x258, 0x0606_0606_0606_0606),
+            (0x259, 0),
+            (0x268, 0x0505_0505_0505_0505),
+            (0x269, 0x0505_0505_0505_0505),
+            (0x26a, 0x0505_0505_0505_0505),
+            (0x26b, 0x0505_0505_0505_0505),
+            (0x26c, 0x0505_0505_0505_0505),
+            (0x26d, 0x0505_0505_0505_0505),
+            (0x26e, 0x0505_0505_0505_0505),
+            (0x26f, 0x0505_0505_0505_0505),
+            // 0-2G WB
+            (0x200, 0x0000_0006),
+            (0x201, 0x7f_8000_0800),
+            // 2G-3G WB
+            (0x202, 0x8000_0006),
+            (0x203, 0x7f_c000_0800),
+            // 2.75G-3G UC，与上一个范围重叠
+            (0x204, 0xb000_0000),
+            (0x205, 0x7f_f000_0800),
+            // 4G-8G WB
+            (0x206, 0x1_0000_0006),
+            (0x207, 0x7f_0000_0800),
+            // 4G-5G WT，与上一个范围重叠
+            (0x208, 0x1_0000_0004),
+            (0x209, 0x7f_c000_0800),
+            // 6G-6G+64M WC，与 WB 重叠，未定义
+            (0x20a, 0x1_8000_0001),
+            (0x20b, 0x7f_fc00_0800),
+            // 无效的范围
+            (0x20c, 0x2_0000_0006),
+            (0x20d, 0x7f_0000_0000),
+        ];
+        FakeMsr::new(&regs)
+    }
+
+    #[test]
+    fn enumerate() {
+        let no_mtrr = StdFeature::default();
+        assert!(Mtrr::new(fake_mtrr(), &no_mtrr).is_none());
+
+        let std_feature = StdFeature {
+            ebx: 0,
+            ecx: 0,
+            edx: 1 << 12,
+        };
+        let mtrr = Mtrr::new(fake_mtrr(), &std_feature).unwrap();
+        assert_eq!(mtrr.cap().variable_count(), 8);
+        assert!(mtrr.cap().support_fixed() && mtrr.cap().support_wc());
+        assert!(mtrr.default_type().enabled());
+        assert_eq!(mtrr.default_type().mem_type(), MemType::UC);
+
+        let ranges: std::vec::Vec<_> = mtrr.variable_ranges(&AddressWidths::TEST).collect();
+        assert_eq!(ranges.len(), 6);
+        assert_eq!(
+            (ranges[0].base, ranges[0].size, ranges[0].mem_type),
+            (0, 0x8000_0000, MemType::WB)
+        );
+        assert_eq!(
+            (ranges[3].base, ranges[3].size),
+            (0x1_0000_0000, 0x1_0000_0000)
+        );
+        assert_eq!(ranges[5].size, 0x400_0000);
+        assert!(mtrr.variable_range(6, &AddressWidths::TEST).is_none());
+        assert!(mtrr.variable_range(8, &AddressWidths::TEST).is_none());
+
+        let fixed = mtrr.fixed_ranges().unwrap();
+        assert_eq!(fixed.mem_type(0x9_ffff), Some(MemType::WB));
+        assert_eq!(fixed.mem_type(0xa_0000), Some(MemType::UC));
+        assert_eq!(fixed.mem_type(0xf_f000), Some(MemType::WP));
+        assert_eq!(fixed.mem_type(FixedRanges::LIMIT), None);
+        assert_eq!(fixed.iter().nth(24), Some((0xc_0000, 0x1000, MemType::WP)));
+    }
+
+    #[test]
+    fn effective_type() {
+        let std_feature = StdFeature {
+            ebx: 0,
+            ecx: 0,
+            edx: 1 << 12,
+        };
+        let mut state = Mtrr::new(fake_mtrr(), &std_feature)
+            .unwrap()
+            .state(&AddressWidths::TEST);
+
+        assert_eq!(state.mem_type(0x10_0000), Some(MemType::WB));
+        assert_eq!(state.mem_type(0xb800_0000), Some(MemType::UC));
+        assert_eq!(state.mem_type(0xc000_0000), Some(MemType::UC));
+        assert_eq!(state.mem_type(0x1_0000_0000), Some(MemType::WT));
+        assert_eq!(state.mem_type(0x1_4000_0000), Some(MemType::WB));
+        assert_eq!(state.mem_type(0x1_8000_0000), None);
+
+        assert_eq!(
+            state.effective_type(0x10_0000, 0x1000_0000),
+            Some(MemType::WB)
+        );
+        assert_eq!(
+            state.effective_type(0xb000_0000, 0x1000_0000),
+            Some(MemType::UC)
+        );
+        assert_eq!(state.effective_type(0xc_0000, 0x4_0000), Some(MemType::WP));
+        // 跨越不同类型的范围
+        assert_eq!(state.effective_type(0x9_0000, 0x2_0000), None);
+        assert_eq!(state.effective_type(0xf_f000, 0x2000), None);
+        assert_eq!(state.effective_type(0xa000_0000, 0x2000_0000), None);
+        assert_eq!(state.effective_type(0x1_2000_0000, 0x4000_0000), None);
+
+        state.enabled = false;
+        assert_eq!(
+            state.effective_type(0x1_8000_0000, 0x1000),
+            Some(MemType::UC)
+        );
+
+        let mut state = MtrrState::new(MemType::WB);
+        assert_eq!(state.effective_type(0, u64::MAX), Some(MemType::WB));
+        state.fixed = Some(FixedRanges::from_raw(&[0x0606_0606_0606_0606; 11]));
+        assert_eq!(state.effective_type(0, 0x20_0000), Some(MemType::WB));
+    }
+}
@@ -0,0 +1,536 @@
+use bits::field::BufferWriter;
+use register::RegisterBufferReader;
+
+use crate::{
+    cpuid::address::AddressWidths,
+    cr::{
+        cr0::{fields as cr0_fields, Cr0, Cr0Buffer},
+        cr3::{Cr3, Cr3Buffer},
+        cr4::{fields as cr4_fields, Cr4, Cr4Buffer},
+        flags::{fields as flags_fields, Flags},
+    },
+    msr::MsrAccess,
+    ArchError, Clean,
+};
+
+use super::{
+    fields, FixedRanges, MemType, Mtrr, MtrrDefaultType, MtrrDefaultTypeBuffer, MtrrFix16K,
+    MtrrFix4K, MtrrFix64K, MtrrPhys, MtrrPhysBaseBuffer, MtrrPhysMaskBuffer, MtrrState,
+    VariableRange,
+};
+
+impl VariableRange {
+    /// 构建一个可变范围：
+    ///
+    /// 1. `mem_type` 需要是 MTRR 所支持的类型，否则返回错误 `ArchError::InvalidMemType`；
+    /// 2. `size` 需要是 2 的幂并且不小于 4KB，`base` 需要按 `size` 对齐，否则返回错误 `ArchError::AddressNotAligned`；
+    /// 3. 范围不能超出 MAXPHYADDR，否则返回错误 `ArchError::AddressExceedsPhysicalWidth`。
+    pub fn new(
+        index: u8,
+        base: u64,
+        size: u64,
+        mem_type: MemType,
+        widths: &AddressWidths,
+    ) -> Result<Self, ArchError> {
+        if !mem_type.is_valid_for_mtrr() {
+            return Err(ArchError::InvalidMemType);
+        }
+        if size < 0x1000 || !size.is_power_of_two() || base & (size - 1) != 0 {
+            return Err(ArchError::AddressNotAligned);
+        }
+        match base.checked_add(size - 1) {
+            Some(last) if widths.is_valid_physical(last) => {}
+            _ => return Err(ArchError::AddressExceedsPhysicalWidth),
+        }
+        Ok(Self {
+            index,
+            base,
+            mask: !(size - 1) & widths.physical_mask(),
+            size,
+            mem_type,
+        })
+    }
+
+    /// 两个范围是否重叠：在双方掩码共同覆盖的 bit 上，两个基址相同。
+    fn overlaps(&self, other: &VariableRange) -> bool {
+        let mask = self.mask & other.mask;
+        self.base & mask == other.base & mask
+    }
+}
+
+impl MtrrState {
+    /// 检查 `range` 与其他编号的可变范围重叠时，内存类型的组合是否有定义，
+    /// 否则返回错误 `ArchError::MemTypeConflict`。
+    pub fn check_conflict(&self, range: &VariableRange) -> Result<(), ArchError> {
+        let conflict = self
+            .variable_ranges()
+            .filter(|other| other.index != range.index && other.overlaps(range))
+            .any(|other| other.mem_type.overlap(range.mem_type).is_none());
+        if conflict {
+            return Err(ArchError::MemTypeConflict);
+        }
+        Ok(())
+    }
+}
+
+/// 回写并无效化所有缓存
+#[inline]
+unsafe fn wbinvd() {
+    asm!("wbinvd", options(nostack, preserves_flags));
+}
+
+/// # 修改 MTRR 时的缓存控制
+///
+/// 修改 MTRR 前需要屏蔽中断、禁用缓存并刷新缓存和 TLB，修改后再恢复。
+/// [`MtrrUpdate`] 只负责 MSR 的读写，这两步由本 trait 完成，通常使用 [`CrCacheControl`]。
+pub trait CacheControl {
+    /// 在禁用 MTRR 之前调用
+    unsafe fn disable(&mut self);
+    /// 在重新写入 IA32_MTRR_DEF_TYPE 之后调用
+    unsafe fn restore(&mut self);
+}
+
+/// # 通过控制寄存器禁用缓存
+///
+/// 按照 Intel SDM 11.11.7.2 中的流程，[`disable`](CacheControl::disable) 依次：
+///
+/// 1. 保存并屏蔽中断；
+/// 2. `CR0.CD = 1, CR0.NW = 0`，进入 no-fill 缓存模式；
+/// 3. 执行 WBINVD；
+/// 4. 若 `CR4.PGE = 1` 则清 0，否则重新写入 CR3，以刷新 TLB。
+///
+/// [`restore`](CacheControl::restore) 依次：
+///
+/// 1. 执行 WBINVD，并刷新 TLB；
+/// 2. 恢复 `CR0.CD` 和 `CR0.NW`；
+/// 3. 恢复 `CR4.PGE`；
+/// 4. 恢复中断。
+pub struct CrCacheControl {
+    cr0: Option<Clean<Cr0Buffer>>,
+    cr3: Option<Clean<Cr3Buffer>>,
+    cr4: Option<Clean<Cr4Buffer>>,
+    saved_cd: bool,
+    saved_nw: bool,
+    pge: bool,
+    interrupt: bool,
+}
+
+impl CrCacheControl {
+    /// CR0、CR3、CR4 的缓冲区已被占用时返回 None。
+    pub fn new(cr0: &Cr0, cr3: &Cr3, cr4: &Cr4) -> Option<Self> {
+        Some(Self {
+            cr0: Some(cr0.buffer()?),
+            cr3: Some(cr3.buffer()?),
+            cr4: Some(cr4.buffer()?),
+            saved_cd: false,
+            saved_nw: false,
+            pge: false,
+            interrupt: false,
+        })
+    }
+}
+
+impl CacheControl for CrCacheControl {
+    unsafe fn disable(&mut self) {
+        self.interrupt = Flags::buffer().read::<flags_fields::IF>();
+        Flags::disable_if();
+
+        if let (Some(cr0), Some(cr3), Some(cr4)) =
+            (self.cr0.take(), self.cr3.take(), self.cr4.take())
+        {
+            self.saved_cd = cr0.read::<cr0_fields::CD>();
+            self.saved_nw = cr0.read::<cr0_fields::NW>();
+            self.cr0 = Some(
+                cr0.write::<cr0_fields::CD>(true)
+                    .write::<cr0_fields::NW>(false)
+                    .flush(),
+            );
+            wbinvd();
+
+            self.pge = cr4.read::<cr4_fields::PGE>();
+            if self.pge {
+                self.cr3 = Some(cr3);
+                self.cr4 = Some(cr4.write::<cr4_fields::PGE>(false).flush());
+            } else {
+                self.cr3 = Some(cr3.asume_dirty().flush());
+                self.cr4 = Some(cr4);
+            }
+        }
+    }
+
+    unsafe fn restore(&mut self) {
+        wbinvd();
+        if let (Some(cr0), Some(cr3), Some(cr4)) =
+            (self.cr0.take(), self.cr3.take(), self.cr4.take())
+        {
+            // PGE 为 0 时，重新写入 CR3 即可刷新 TLB
+            cr3.asume_dirty().flush();
+            cr0.write::<cr0_fields::CD>(self.saved_cd)
+                .write::<cr0_fields::NW>(self.saved_nw)
+                .flush();
+            // 清 0 后再置 1 PGE，会刷新包括全局页在内的所有 TLB
+            if self.pge {
+                cr4.write::<cr4_fields::PGE>(true).flush();
+            }
+        }
+        if self.interrupt {
+            Flags::enable_if();
+        }
+    }
+}
+
+impl<A: MsrAccess> Mtrr<A> {
+    /// 开始修改 MTRR：先通过 [`CrCacheControl`] 禁用缓存，再令 `IA32_MTRR_DEF_TYPE.E = 0` 禁用 MTRR。
+    ///
+    /// 返回的 [`MtrrUpdate`] 被 drop 时，会按照相反的顺序恢复上述状态。
+    /// CR0、CR3、CR4 的缓冲区已被占用时返回 None。
+    ///
+    /// ⚠️ 多处理器系统中，所有处理器需要同时执行该流程，并且最终的 MTRR 需要保持一致。
+    pub unsafe fn update<'a>(
+        &'a mut self,
+        cr0: &Cr0,
+        cr3: &Cr3,
+        cr4: &Cr4,
+        widths: &AddressWidths,
+    ) -> Option<MtrrUpdate<'a, A>> {
+        let cache = CrCacheControl::new(cr0, cr3, cr4)?;
+        Some(self.update_with(cache, widths))
+    }
+
+    /// 与 [`update`](Self::update) 相同，但禁用和恢复缓存由 `cache` 完成。
+    pub unsafe fn update_with<C: CacheControl>(
+        &mut self,
+        mut cache: C,
+        widths: &AddressWidths,
+    ) -> MtrrUpdate<'_, A, C> {
+        let state = self.state(widths);
+        let def_type = self.default_type();
+
+        cache.disable();
+        let mut disabled = MtrrDefaultTypeBuffer {
+            data: def_type.data,
+        };
+        disabled.write::<fields::E>(false);
+        self.access
+            .write_msr(MtrrDefaultType::REG_ADDR, disabled.data);
+
+        MtrrUpdate {
+            mtrr: self,
+            widths: *widths,
+            state,
+            def_type,
+            cache,
+        }
+    }
+}
+
+/// # MTRR 修改事务
+///
+/// 由 [`Mtrr::update`] 或 [`Mtrr::update_with`] 创建，期间 MTRR 处于禁用状态，每次修改前都会检查参数，
+/// 检查失败时不会写入任何寄存器。drop 时（或调用 [`commit`](Self::commit)）
+/// 先恢复 IA32_MTRR_DEF_TYPE 以重新使能 MTRR，再通过 [`CacheControl::restore`] 恢复缓存。
+///
+/// ```not test
+/// let mut update = unsafe { mtrr.update(&arch.cr0, &arch.cr3, &cr4, &widths)? };
+/// update
+///     .set_variable(2, 0xe000_0000, 0x1000_0000, MemType::WC)?
+///     .clear_variable(3)?;
+/// update.commit();
+/// ```
+pub struct MtrrUpdate<'a, A: MsrAccess, C: CacheControl = CrCacheControl> {
+    mtrr: &'a mut Mtrr<A>,
+    widths: AddressWidths,
+    state: MtrrState,
+    /// 事务结束时写回的值
+    def_type: MtrrDefaultTypeBuffer,
+    cache: C,
+}
+
+impl<'a, A: MsrAccess, C: CacheControl> MtrrUpdate<'a, A, C> {
+    /// 事务结束后 MTRR 的状态
+    pub fn state(&self) -> &MtrrState {
+        &self.state
+    }
+
+    fn check_mem_type(&self, mem_type: MemType) -> Result<(), ArchError> {
+        if !mem_type.is_valid_for_mtrr() {
+            return Err(ArchError::InvalidMemType);
+        }
+        if mem_type == MemType::WC && !self.mtrr.cap.support_wc() {
+            return Err(ArchError::FeatureIsNotSupported);
+        }
+        Ok(())
+    }
+
+    /// 将第 `index` 个可变范围设置为 `[base, base + size)`，参数要求参见 [`VariableRange::new`]。
+    ///
+    /// + `index` 超出 VCNT 时返回错误 `ArchError::IndexOutOfRange`；
+    /// + 处理器不支持 WC 时返回错误 `ArchError::FeatureIsNotSupported`；
+    /// + 与其他范围冲突时返回错误 `ArchError::MemTypeConflict`。
+    pub fn set_variable(
+        &mut self,
+        index: u8,
+        base: u64,
+        size: u64,
+        mem_type: MemType,
+    ) -> Result<&mut Self, ArchError> {
+        if index >= self.mtrr.cap.variable_count() {
+            return Err(ArchError::IndexOutOfRange);
+        }
+        self.check_mem_type(mem_type)?;
+        let range = VariableRange::new(index, base, size, mem_type, &self.widths)?;
+        self.state.check_conflict(&range)?;
+
+        let mut base_buffer = MtrrPhysBaseBuffer { data: 0 };
+        base_buffer
+            .set_phys_base(range.base, &self.widths)?
+            .write::<fields::Type>(mem_type);
+        let mut mask_buffer = MtrrPhysMaskBuffer { data: 0 };
+        mask_buffer
+            .set_phys_mask(range.mask, &self.widths)?
+            .write::<fields::V>(true);
+        self.state
+            .set_variable(range)
+            .ok_or(ArchError::IndexOutOfRange)?;
+
+        let access = &mut self.mtrr.access;
+        access.write_msr(MtrrPhys::base_reg_addr(index), base_buffer.data);
+        access.write_msr(MtrrPhys::mask_reg_addr(index), mask_buffer.data);
+        Ok(self)
+    }
+
+    /// 清除第 `index` 个可变范围的 V 位，`index` 超出 VCNT 时返回错误 `ArchError::IndexOutOfRange`。
+    pub fn clear_variable(&mut self, index: u8) -> Result<&mut Self, ArchError> {
+        if index >= self.mtrr.cap.variable_count() {
+            return Err(ArchError::IndexOutOfRange);
+        }
+        self.state.remove_variable(index);
+        let access = &mut self.mtrr.access;
+        access.write_msr(MtrrPhys::mask_reg_addr(index), 0);
+        access.write_msr(MtrrPhys::base_reg_addr(index), 0);
+        Ok(self)
+    }
+
+    /// 写入所有固定范围，并在事务结束时使能固定范围 MTRR（FE = 1）。
+    ///
+    /// 处理器不支持固定范围 MTRR 时返回错误 `ArchError::FeatureIsNotSupported`。
+    pub fn set_fixed(&mut self, fixed: &FixedRanges) -> Result<&mut Self, ArchError> {
+        if !self.mtrr.cap.support_fixed() {
+            return Err(ArchError::FeatureIsNotSupported);
+        }
+        for (_, _, mem_type) in fixed.iter() {
+            self.check_mem_type(mem_type)?;
+        }
+        let regs = fixed.raw();
+        let addrs = [
+            MtrrFix64K::REG_ADDR,
+            MtrrFix16K::LOW_REG_ADDR,
+            MtrrFix16K::TOP_REG_ADDR,
+        ]
+        .iter()
+        .copied()
+        .chain(MtrrFix4K::LOW_REG_ADDR..=MtrrFix4K::TOP_REG_ADDR);
+        for (addr, &value) in addrs.zip(regs.iter()) {
+            self.mtrr.access.write_msr(addr, value);
+        }
+        self.def_type.write::<fields::FE>(true);
+        self.state.fixed = Some(*fixed);
+        Ok(self)
+    }
+
+    /// 未被任何 MTRR 覆盖的物理地址的内存类型
+    pub fn set_default_type(&mut self, mem_type: MemType) -> Result<&mut Self, ArchError> {
+        self.check_mem_type(mem_type)?;
+        self.def_type.write::<fields::Type>(mem_type);
+        self.state.default_type = mem_type;
+        Ok(self)
+    }
+
+    /// 事务结束时是否使能 MTRR，默认保持原状态。
+    pub fn set_enabled(&mut self, enabled: bool) -> &mut Self {
+        self.def_type.write::<fields::E>(enabled);
+        self.state.enabled = enabled;
+        self
+    }
+
+    /// 结束事务，等同于 drop。
+    pub fn commit(self) {}
+}
+
+impl<'a, A: MsrAccess, C: CacheControl> Drop for MtrrUpdate<'a, A, C> {
+    fn drop(&mut self) {
+        self.mtrr
+            .access
+            .write_msr(MtrrDefaultType::REG_ADDR, self.def_type.data);
+        unsafe {
+            self.cache.restore();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::RefCell, vec, vec::Vec};
+
+    use crate::{
+        cpuid::{address::AddressWidths, feature::StdFeature},
+        mem::mttr::{test::fake_mtrr, MemType, Mtrr, MtrrState, VariableRange},
+        ArchError,
+    };
+
+    use super::CacheControl;
+
+    #[test]
+    fn validate_range() {
+        let range = VariableRange::new(
+            0,
+            0x8000_0000,
+            0x4000_0000,
+            MemType::WB,
+            &AddressWidths::TEST,
+        )
+        .unwrap();
+        assert_eq!(range.mask, 0x7f_c000_0000);
+        assert!(range.contains(0xbfff_ffff) && !range.contains(0xc000_0000));
+
+        let new = |base, size, mem_type| {
+            VariableRange::new(1, base, size, mem_type, &AddressWidths::TEST)
+        };
+        assert!(matches!(
+            new(0, 0x3000_0000, MemType::WB),
+            Err(ArchError::AddressNotAligned)
+        ));
+        assert!(matches!(
+            new(0x1000_0000, 0x2000_0000, MemType::WB),
+            Err(ArchError::AddressNotAligned)
+        ));
+        assert!(matches!(
+            new(0, 0x800, MemType::WB),
+            Err(ArchError::AddressNotAligned)
+        ));
+        assert!(matches!(
+            new(0x80_0000_0000, 0x1000, MemType::WB),
+            Err(ArchError::AddressExceedsPhysicalWidth)
+        ));
+        assert!(matches!(
+            new(0, 0x1000, MemType::UC_MINUS),
+            Err(ArchError::InvalidMemType)
+        ));
+    }
+
+    #[test]
+    fn conflict() {
+        let mut state = MtrrState::new(MemType::UC);
+        let wb = VariableRange::new(0, 0, 0x8000_0000, MemType::WB, &AddressWidths::TEST).unwrap();
+        state.set_variable(wb).unwrap();
+
+        let new = |index, base, size, mem_type| {
+            VariableRange::new(index, base, size, mem_type, &AddressWidths::TEST).unwrap()
+        };
+        assert!(state
+            .check_conflict(&new(1, 0x7000_0000, 0x1000_0000, MemType::UC))
+            .is_ok());
+        assert!(state
+            .check_conflict(&new(1, 0x7000_0000, 0x1000_0000, MemType::WT))
+            .is_ok());
+        assert!(state
+            .check_conflict(&new(1, 0x8000_0000, 0x1000_0000, MemType::WC))
+            .is_ok());
+        assert!(matches!(
+            state.check_conflict(&new(1, 0x7000_0000, 0x1000_0000, MemType::WC)),
+            Err(ArchError::MemTypeConflict)
+        ));
+        // 替换自身不算冲突
+        assert!(state
+            .check_conflict(&new(0, 0, 0x1000_0000, MemType::WC))
+            .is_ok());
+
+        state
+            .set_variable(new(0, 0, 0x1000_0000, MemType::WC))
+            .unwrap();
+        assert_eq!(state.variable_ranges().count(), 1);
+        assert_eq!(state.mem_type(0x1000_0000), Some(MemType::UC));
+        assert!(state.remove_variable(0).is_some());
+        assert_eq!(state.mem_type(0), Some(MemType::UC));
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    enum Event {
+        Disable,
+        Restore,
+    }
+
+    struct FakeCache<'a> {
+        log: &'a RefCell<Vec<Event>>,
+    }
+
+    impl CacheControl for FakeCache<'_> {
+        unsafe fn disable(&mut self) {
+            self.log.borrow_mut().push(Event::Disable);
+        }
+        unsafe fn restore(&mut self) {
+            self.log.borrow_mut().push(Event::Restore);
+        }
+    }
+
+    #[test]
+    fn transaction() {
+        let std_feature = StdFeature {
+            ebx: 0,
+            ecx: 0,
+            edx: 1 << 12,
+        };
+        let mut mtrr = Mtrr::new(fake_mtrr(), &std_feature).unwrap();
+        let mut fixed = mtrr.fixed_ranges().unwrap();
+        fixed.set(0xc_0000, 0x8000, MemType::UC);
+        let log = RefCell::new(Vec::new());
+
+        let mut update = unsafe { mtrr.update_with(FakeCache { log: &log }, &AddressWidths::TEST) };
+        assert_eq!(*log.borrow(), [Event::Disable]);
+        update
+            .set_variable(6, 0x2_0000_0000, 0x1000_0000, MemType::WT)
+            .unwrap()
+            .clear_variable(5)
+            .unwrap()
+            .set_fixed(&fixed)
+            .unwrap();
+        // 检查失败时不写入任何寄存器
+        assert!(matches!(
+            update.set_variable(7, 0x7000_0000, 0x1000_0000, MemType::WC),
+            Err(ArchError::MemTypeConflict)
+        ));
+        assert!(matches!(
+            update.clear_variable(8),
+            Err(ArchError::IndexOutOfRange)
+        ));
+        update.commit();
+        assert_eq!(log.into_inner(), [Event::Disable, Event::Restore]);
+
+        let wb = 0x0606_0606_0606_0606;
+        let wp = 0x0505_0505_0505_0505;
+        assert_eq!(
+            mtrr.access.writes,
+            vec![
+                // 禁用 MTRR，保留 FE 和默认类型
+                (0x2ff, 0x0400),
+                (0x20c, 0x2_0000_0004),
+                (0x20d, 0x7f_f000_0800),
+                (0x20b, 0),
+                (0x20a, 0),
+                (0x250, wb),
+                (0x258, wb),
+                (0x259, 0),
+                (0x268, 0),
+                (0x269, wp),
+                (0x26a, wp),
+                (0x26b, wp),
+                (0x26c, wp),
+                (0x26d, wp),
+                (0x26e, wp),
+                (0x26f, wp),
+                // 恢复 E 和 FE
+                (0x2ff, 0x0c00),
+            ]
+        );
+    }
+}
@@ -2,20 +2,41 @@ use core::fmt::Display;
 
 use bits::field::BufferReader;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Selector {
     pub(in crate::mem::segment) data: u16,
 }
 impl_buffer_trait!(Selector);
 
 /// [`Self::RPL0`]、[`Self::DPL0`]、[`Self::PL0`] 数值上是等价的，也可以互相比较，仅存在语义上的区别。
-#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 pub struct Privilege {
     pub(in crate::mem) data: u8,
 }
 impl Selector {
+    /// `index` 为描述符表中的条目索引，`ti` 为 true 时引用 LDT。
+    pub const fn new(index: u16, ti: bool, rpl: Privilege) -> Self {
+        Self {
+            data: (index << 3) | ((ti as u16) << 2) | rpl.data as u16,
+        }
+    }
+    pub const fn from_raw(data: u16) -> Self {
+        Self { data }
+    }
+    pub const fn raw(&self) -> u16 {
+        self.data
+    }
     pub fn rpl(&self) -> Privilege {
         self.read::<fields::RPL>()
     }
+    /// 描述符表中的条目索引
+    pub fn index(&self) -> u16 {
+        self.read::<fields::SI>()
+    }
+    /// 是否引用 LDT
+    pub fn ti(&self) -> bool {
+        self.read::<fields::TI>()
+    }
 }
 
 impl Display for Privilege {
@@ -1,13 +1,86 @@
 use core::marker::PhantomData;
 
+use register::{RegisterBufferFlush, RegisterBufferReader, RegisterBufferWriter};
+
 use crate::{
     cpuid::feature::StdFeature,
     mem::segment::{cs::Cs, selector::Privilege},
+    Clean,
 };
 
+/// 为 MSR 缓冲区实现 [`MsrBuffer`]、`RegisterBufferFlush` 以及读写字段所需的 trait，
+/// 缓冲区必须是只含有 `data: u64` 字段的结构体。
+///
+/// 没有实现 [`ModelSpecificRegister`] 的寄存器可以通过 `@addr` 直接给出地址。
+///
+/// ```not test
+/// impl_msr_buffer!(Efer => EferBuffer);
+/// impl_msr_buffer!(@addr Syscfg::REG_ADDR => SyscfgBuffer);
+/// ```
+macro_rules! impl_msr_buffer {
+    (@addr $addr:expr => $Buffer:ident) => {
+        impl $crate::msr::MsrBuffer for $Buffer {
+            #[inline]
+            fn from_raw(data: u64) -> Self {
+                Self { data }
+            }
+            #[inline]
+            fn raw(&self) -> u64 {
+                self.data
+            }
+        }
+        impl register::RegisterBufferFlush for $Buffer {
+            #[inline]
+            fn flush(&mut self) {
+                unsafe {
+                    $crate::msr::wrmsr(
+                        $addr,
+                        self.data as u32,
+                        (self.data >> 32) as u32,
+                    );
+                }
+            }
+        }
+        impl_reg_buffer_trait!($Buffer);
+    };
+    ($($Reg:ty => $Buffer:ident);+ $(;)?) => {
+        $(
+            impl_msr_buffer!(@addr <$Reg as $crate::msr::ModelSpecificRegister>::REG_ADDR => $Buffer);
+        )+
+    };
+}
+
+pub mod apic;
 pub mod efer;
+pub mod fsgs;
+pub mod pat;
+pub mod syscall;
 pub mod syscfg;
 
+/// # Model-specific 寄存器
+///
+/// 新增一个 MSR 只需要：
+///
+/// 1. 定义一个空结构体作为寄存器类型，为其实现本 trait；
+/// 2. 定义只含有 `data: u64` 字段的缓冲区，通过 `impl_msr_buffer!` 实现读写和刷新；
+/// 3. 通过 `bits::fields_ex!` 定义缓冲区中的字段。
+///
+/// 之后即可通过 [`Msr::buffer`] 读取寄存器，修改字段后调用 [`Dirty::flush`](crate::Dirty::flush) 写回。
+///
+/// 只在部分制造商的处理器上存在的寄存器（例如 [`Syscfg`](syscfg::Syscfg)）不应实现本 trait，
+/// 而是提供检查处理器制造商的读取函数，避免通过 [`Msr::buffer`] 触发 #GP 异常。
+pub trait ModelSpecificRegister {
+    /// 寄存器地址
+    const REG_ADDR: u32;
+    type Buffer: MsrBuffer;
+}
+
+/// MSR 缓冲区，保存寄存器的 64 bit 原始值，刷新时整体写回。
+pub trait MsrBuffer: RegisterBufferReader + RegisterBufferWriter + RegisterBufferFlush {
+    fn from_raw(data: u64) -> Self;
+    fn raw(&self) -> u64;
+}
+
 #[derive(Clone, Copy)]
 pub struct Msr {
     pub(crate) phatom: PhantomData<usize>,
@@ -28,6 +101,14 @@ impl Msr {
         }
     }
 
+    /// 读取寄存器 `R`，修改字段后通过 [`Dirty::flush`](crate::Dirty::flush) 写回（读-改-写）。
+    #[inline]
+    pub fn buffer<R: ModelSpecificRegister>(&self) -> Clean<R::Buffer> {
+        Clean {
+            raw_buffer: R::Buffer::from_raw(self.read(R::REG_ADDR)),
+        }
+    }
+
     pub fn read(&self, addr: u32) -> u64 {
         unsafe { rdmsr(addr) }
     }
@@ -36,6 +117,53 @@ impl Msr {
     }
 }
 
+/// # MSR 读写接口
+///
+/// 需要读写一组地址不固定的寄存器（例如 MTRR）时使用，测试时可以替换为伪造的寄存器。
+pub trait MsrAccess {
+    fn read_msr(&self, addr: u32) -> u64;
+    fn write_msr(&mut self, addr: u32, value: u64);
+}
+
+impl MsrAccess for Msr {
+    #[inline]
+    fn read_msr(&self, addr: u32) -> u64 {
+        self.read(addr)
+    }
+    #[inline]
+    fn write_msr(&mut self, addr: u32, value: u64) {
+        self.write(addr, value as u32, (value >> 32) as u32)
+    }
+}
+
+/// 测试用的伪造寄存器，未写入过的寄存器读出为 0，所有写入按顺序记录在 `writes` 中。
+#[cfg(test)]
+pub(crate) struct FakeMsr {
+    pub(crate) regs: std::collections::BTreeMap<u32, u64>,
+    pub(crate) writes: std::vec::Vec<(u32, u64)>,
+}
+
+#[cfg(test)]
+impl FakeMsr {
+    pub(crate) fn new(regs: &[(u32, u64)]) -> Self {
+        Self {
+            regs: regs.iter().copied().collect(),
+            writes: std::vec::Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+impl MsrAccess for FakeMsr {
+    fn read_msr(&self, addr: u32) -> u64 {
+        self.regs.get(&addr).copied().unwrap_or(0)
+    }
+    fn write_msr(&mut self, addr: u32, value: u64) {
+        self.regs.insert(addr, value);
+        self.writes.push((addr, value));
+    }
+}
+
 /// ## 读 model-specific 寄存器
 /// 只能在特权级别为 0 时调用执行，否则会触发通用保护异常（#GP）
 #[inline]
@@ -0,0 +1,227 @@
+use crate::{
+    cpuid::{address::AddressWidths, feature::StdFeature},
+    ArchError, Clean, Dirty,
+};
+
+use super::ModelSpecificRegister;
+
+/// # APIC 基址寄存器
+/// IA32_APIC_BASE
+///
+/// 地址为 1Bh，控制本地 APIC 的使能、工作模式以及 xAPIC 模式下 MMIO 寄存器的物理基址。
+pub struct ApicBase;
+
+impl ModelSpecificRegister for ApicBase {
+    const REG_ADDR: u32 = 0x001B;
+    type Buffer = ApicBaseBuffer;
+}
+
+impl ApicBase {
+    /// 需要 `CPUID.01H:EDX.APIC[9] = 1`，否则不存在该寄存器。
+    pub fn is_supported(std_feature: &StdFeature) -> bool {
+        std_feature.support_apic()
+    }
+}
+
+pub struct ApicBaseBuffer {
+    data: u64,
+}
+
+impl_msr_buffer!(ApicBase => ApicBaseBuffer);
+
+/// # 本地 APIC 模式
+///
+/// 由 EN 和 EXTD 两个 bit 决定，`EN = 0, EXTD = 1` 为无效的组合。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApicMode {
+    /// `EN = 0, EXTD = 0`
+    Disabled,
+    /// `EN = 1, EXTD = 0`，通过 MMIO 访问寄存器
+    XApic,
+    /// `EN = 1, EXTD = 1`，通过 MSR 访问寄存器
+    X2Apic,
+}
+
+impl ApicMode {
+    fn from_bits(en: bool, extd: bool) -> Option<Self> {
+        match (en, extd) {
+            (false, false) => Some(ApicMode::Disabled),
+            (true, false) => Some(ApicMode::XApic),
+            (true, true) => Some(ApicMode::X2Apic),
+            (false, true) => None,
+        }
+    }
+
+    /// 模式之间的转换是否合法，非法的转换会导致 #GP 异常：
+    ///
+    /// + 禁用 → xAPIC，xAPIC → x2APIC；
+    /// + 任意模式 → 禁用；
+    /// + 禁用 → x2APIC、x2APIC → xAPIC 均不合法，x2APIC 只能先禁用，再切换到 xAPIC。
+    pub fn can_switch_to(self, target: ApicMode) -> bool {
+        matches!(
+            (self, target),
+            (ApicMode::Disabled, ApicMode::XApic)
+                | (ApicMode::XApic, ApicMode::X2Apic)
+                | (_, ApicMode::Disabled)
+        ) || self == target
+    }
+}
+
+impl Clean<ApicBaseBuffer> {
+    /// 当前处理器是否为引导处理器（BSP）
+    pub fn is_bsp(&self) -> bool {
+        self.read::<fields::BSP>()
+    }
+    /// EN、EXTD 为无效的组合时返回 None
+    pub fn mode(&self) -> Option<ApicMode> {
+        ApicMode::from_bits(self.read::<fields::EN>(), self.read::<fields::EXTD>())
+    }
+    /// xAPIC 模式下 MMIO 寄存器的物理基址，4KB 对齐。
+    pub fn base(&self) -> u64 {
+        self.read::<fields::BASE>() << 12
+    }
+
+    /// 切换到 `target` 模式，合法的转换参见 [`ApicMode::can_switch_to`]：
+    ///
+    /// + 处理器不支持本地 APIC（`CPUID.01H:EDX.APIC[9] = 0`）时切换到 xAPIC 或 x2APIC，返回错误 `ArchError::FeatureIsNotSupported`；
+    /// + 处理器不支持 x2APIC（`CPUID.01H:ECX.x2APIC[21] = 0`）时切换到 x2APIC，返回错误 `ArchError::FeatureIsNotSupported`；
+    /// + 非法的转换返回错误 `ArchError::InvalidModeTransition`。
+    ///
+    /// 每次转换都需要单独写回寄存器，例如从禁用切换到 x2APIC 时，需要先切换到 xAPIC 并 flush。
+    pub fn switch_mode(
+        self,
+        target: ApicMode,
+        std_feature: &StdFeature,
+    ) -> Result<Dirty<ApicBaseBuffer>, ArchError> {
+        if target != ApicMode::Disabled && !ApicBase::is_supported(std_feature) {
+            return Err(ArchError::FeatureIsNotSupported);
+        }
+        if target == ApicMode::X2Apic && !std_feature.support_x2apic() {
+            return Err(ArchError::FeatureIsNotSupported);
+        }
+        match self.mode() {
+            Some(current) if current.can_switch_to(target) => {}
+            _ => return Err(ArchError::InvalidModeTransition),
+        }
+        let (en, extd) = match target {
+            ApicMode::Disabled => (false, false),
+            ApicMode::XApic => (true, false),
+            ApicMode::X2Apic => (true, true),
+        };
+        Ok(self.write::<fields::EN>(en).write::<fields::EXTD>(extd))
+    }
+}
+
+impl Dirty<ApicBaseBuffer> {
+    /// 写入 xAPIC 模式下 MMIO 寄存器的物理基址，地址需要 4KB 对齐，并且不能超出 MAXPHYADDR，
+    /// 否则分别返回错误 `ArchError::AddressNotAligned` 和 `ArchError::AddressExceedsPhysicalWidth`。
+    pub fn set_base(self, addr: u64, widths: &AddressWidths) -> Result<Self, ArchError> {
+        if addr & 0xfff != 0 {
+            return Err(ArchError::AddressNotAligned);
+        }
+        if !widths.is_valid_physical(addr) {
+            return Err(ArchError::AddressExceedsPhysicalWidth);
+        }
+        Ok(self.write::<fields::BASE>(addr >> 12))
+    }
+}
+
+pub mod fields {
+    bits::fields_ex! {
+        super::ApicBaseBuffer [data] {
+            /// APIC 基址，不保存低 12bit（永远为 0）
+            pub(super) BASE [12..=51, rw, u64],
+            /// APIC 全局使能
+            pub(super) EN   [11, rw, bool],
+            /// x2APIC 模式使能
+            pub(super) EXTD [10, rw, bool],
+            /// 引导处理器标志，由处理器在复位时设置
+            pub BSP         [08, ro, bool],
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        cpuid::{address::AddressWidths, feature::StdFeature},
+        msr::MsrBuffer,
+        ArchError, Clean,
+    };
+
+    use super::{ApicBaseBuffer, ApicMode};
+
+    fn buffer(data: u64) -> Clean<ApicBaseBuffer> {
+        Clean {
+            raw_buffer: ApicBaseBuffer::from_raw(data),
+        }
+    }
+
+    #[test]
+    fn mode_transition() {
+        let std_feature = StdFeature {
+            ebx: 0,
+            ecx: 1 << 21,
+            edx: 1 << 9,
+        };
+        // 复位后的默认值：BSP，xAPIC，基址 FEE0_0000h
+        let apic = buffer(0xfee0_0900);
+        assert!(apic.is_bsp());
+        assert_eq!(apic.mode(), Some(ApicMode::XApic));
+        assert_eq!(apic.base(), 0xfee0_0000);
+
+        let x2apic = apic.switch_mode(ApicMode::X2Apic, &std_feature).unwrap();
+        assert_eq!(x2apic.raw_buffer.raw(), 0xfee0_0d00);
+
+        assert!(matches!(
+            buffer(0xfee0_0d00).switch_mode(ApicMode::XApic, &std_feature),
+            Err(ArchError::InvalidModeTransition)
+        ));
+        let disabled = buffer(0xfee0_0d00)
+            .switch_mode(ApicMode::Disabled, &std_feature)
+            .unwrap();
+        assert_eq!(disabled.raw_buffer.raw(), 0xfee0_0100);
+        assert!(matches!(
+            buffer(0xfee0_0100).switch_mode(ApicMode::X2Apic, &std_feature),
+            Err(ArchError::InvalidModeTransition)
+        ));
+        assert!(matches!(
+            buffer(0xfee0_0400).switch_mode(ApicMode::Disabled, &std_feature),
+            Err(ArchError::InvalidModeTransition)
+        ));
+        assert!(matches!(
+            buffer(0xfee0_0800).switch_mode(ApicMode::X2Apic, &StdFeature::default()),
+            Err(ArchError::FeatureIsNotSupported)
+        ));
+        // 支持 x2APIC 但不支持本地 APIC
+        let no_apic = StdFeature {
+            ebx: 0,
+            ecx: 1 << 21,
+            edx: 0,
+        };
+        assert!(matches!(
+            buffer(0xfee0_0000).switch_mode(ApicMode::XApic, &no_apic),
+            Err(ArchError::FeatureIsNotSupported)
+        ));
+        assert!(matches!(
+            buffer(0xfee0_0800).switch_mode(ApicMode::X2Apic, &no_apic),
+            Err(ArchError::FeatureIsNotSupported)
+        ));
+    }
+
+    #[test]
+    fn set_base() {
+        let widths = AddressWidths {
+            physical: 36,
+            linear: 48,
+            guest_physical: 0,
+        };
+        let apic = buffer(0xfee0_0800).asume_dirty();
+        let apic = apic.set_base(0xfec0_0000, &widths).unwrap();
+        assert_eq!(apic.raw_buffer.raw(), 0xfec0_0800);
+        assert!(matches!(
+            apic.set_base(0x10_0000_0000, &widths),
+            Err(ArchError::AddressExceedsPhysicalWidth)
+        ));
+    }
+}
@@ -1,94 +1,61 @@
-use core::marker::PhantomData;
-
-use register::RegisterBufferFlush;
-
-use crate::{cpuid::feature::StdFeature, Clean};
+use crate::{
+    cpuid::feature::{ExtFeature, StdFeature},
+    ArchError, Clean, Dirty,
+};
 
-use super::Msr;
+use super::{ModelSpecificRegister, Msr};
 
 /// # 扩展特性使能寄存器
 /// Extended StdFeature Enable Register
 ///
 /// EFER 是一个 model-specific 寄存器，其地址为 C000_0080h，
 /// 只能被特权软件读写。
+///
+/// 既可以通过 `msr.buffer::<Efer>()` 读写，也可以通过 [`Efer::inst_checked`] 得到的实例读写。
 pub struct Efer {
     msr: Msr,
 }
 
-static mut EFER_INSTANCE: Option<Efer> = Some(Efer {
-    msr: Msr {
-        phatom: PhantomData,
-    },
-});
-
-impl Drop for Efer {
-    fn drop(&mut self) {
-        unsafe {
-            EFER_INSTANCE.replace(Efer {
-                msr: Msr {
-                    phatom: PhantomData,
-                },
-            });
-        }
-    }
-}
-
-impl From<Msr> for Efer {
-    #[inline]
-    fn from(msr: Msr) -> Self {
-        Efer { msr }
-    }
+impl ModelSpecificRegister for Efer {
+    const REG_ADDR: u32 = 0xC000_0080;
+    type Buffer = EferBuffer;
 }
 
 impl Efer {
-    const REG_ADDR: u32 = 0xC000_0080;
-
+    /// 处理器需要支持 long 模式、NX 或 SYSCALL 中的任意一个扩展特性，否则不存在 EFER 寄存器。
+    pub fn is_supported(ext_feature: &ExtFeature) -> bool {
+        ext_feature.support_long_mode() || ext_feature.support_nx() || ext_feature.support_syscall()
+    }
+    /// 当前特权级不为 0 时返回 None，不检查处理器是否存在 EFER 寄存器，需要检查时使用 [`Efer::inst_checked`]。
     pub fn inst(std_feature: &StdFeature) -> Option<Self> {
-        let mut efer = unsafe { EFER_INSTANCE.take()? };
         // Msr::inst 函数中已经检查了特权情况
         let msr = Msr::inst(std_feature)?;
-        efer.msr = msr;
-        Some(efer)
+        Some(Self { msr })
+    }
+    /// 处理器不存在 EFER 寄存器或当前特权级不为 0 时返回 None。
+    pub fn inst_checked(std_feature: &StdFeature, ext_feature: &ExtFeature) -> Option<Self> {
+        if !Self::is_supported(ext_feature) {
+            return None;
+        }
+        Self::inst(std_feature)
     }
     pub unsafe fn inst_uncheck() -> Option<Self> {
-        EFER_INSTANCE.take()
+        Some(Self {
+            msr: Msr::inst_uncheck(),
+        })
     }
+    /// 与 `msr.buffer::<Efer>()` 相同。返回值保留 `Option` 以兼容原有接口，目前不会返回 None。
     #[inline]
     pub fn buffer(&self) -> Option<Clean<EferBuffer>> {
-        let mut raw_buffer = unsafe { EFER_BUFFER_INSTANCE.take()? };
-        raw_buffer.data = self.msr.read(Self::REG_ADDR) as u32;
-        Some(Clean { raw_buffer })
+        Some(self.msr.buffer::<Efer>())
     }
 }
 
 pub struct EferBuffer {
-    data: u32,
-    msr: Msr,
-}
-
-const EFER_BUFFER_INSTANCE_DEFAULT: EferBuffer = EferBuffer {
-    data: 0,
-    msr: Msr {
-        phatom: PhantomData,
-    },
-};
-
-static mut EFER_BUFFER_INSTANCE: Option<EferBuffer> = Some(EFER_BUFFER_INSTANCE_DEFAULT);
-
-impl Drop for EferBuffer {
-    fn drop(&mut self) {
-        unsafe {
-            EFER_BUFFER_INSTANCE.replace(EFER_BUFFER_INSTANCE_DEFAULT);
-        }
-    }
+    data: u64,
 }
 
-impl RegisterBufferFlush for EferBuffer {
-    #[inline]
-    fn flush(&mut self) {
-        self.msr.write(Efer::REG_ADDR, self.data, 0);
-    }
-}
+impl_msr_buffer!(Efer => EferBuffer);
 
 impl Clean<EferBuffer> {
     pub fn long_mode_activated(&self) -> bool {
@@ -96,19 +63,62 @@ impl Clean<EferBuffer> {
     }
 }
 
-impl_reg_buffer_trait!(EferBuffer);
+impl Dirty<EferBuffer> {
+    /// 使能非执行页保护，需要 `CPUID.(EAX=8000_0001h):EDX[20] = 1`，
+    /// 否则返回错误 `ArchError::FeatureIsNotSupported`。
+    pub fn enable_nxe(self, ext_feature: &ExtFeature) -> Result<Self, ArchError> {
+        if !ext_feature.support_nx() {
+            return Err(ArchError::FeatureIsNotSupported);
+        }
+        Ok(self.write::<fields::NXE>(true))
+    }
+    /// 使能 SYSCALL/SYSRET 指令，需要 `CPUID.(EAX=8000_0001h):EDX[11] = 1`，
+    /// 否则返回错误 `ArchError::FeatureIsNotSupported`。
+    pub fn enable_sce(self, ext_feature: &ExtFeature) -> Result<Self, ArchError> {
+        if !ext_feature.support_syscall() {
+            return Err(ArchError::FeatureIsNotSupported);
+        }
+        Ok(self.write::<fields::SCE>(true))
+    }
+    /// 使能安全虚拟机，需要 `CPUID.(EAX=8000_0001h):ECX[2] = 1`，
+    /// 否则返回错误 `ArchError::FeatureIsNotSupported`。
+    ///
+    /// 即便处理器支持 SVM，BIOS 也可能通过 VM_CR.SVMDIS 禁用该特性，此时写入会导致 #GP 异常。
+    pub fn enable_svme(self, ext_feature: &ExtFeature) -> Result<Self, ArchError> {
+        if !ext_feature.support_svm() {
+            return Err(ArchError::FeatureIsNotSupported);
+        }
+        Ok(self.write::<fields::SVME>(true))
+    }
+    /// 使能 FXSAVE/FXRSTOR 优化，需要 `CPUID.(EAX=8000_0001h):EDX[25] = 1`，
+    /// 否则返回错误 `ArchError::FeatureIsNotSupported`。
+    pub fn enable_ffxsr(self, ext_feature: &ExtFeature) -> Result<Self, ArchError> {
+        if !ext_feature.support_ffxsr() {
+            return Err(ArchError::FeatureIsNotSupported);
+        }
+        Ok(self.write::<fields::FFXSR>(true))
+    }
+    /// 使能转换缓存扩展，需要 `CPUID.(EAX=8000_0001h):ECX[17] = 1`，
+    /// 否则返回错误 `ArchError::FeatureIsNotSupported`。
+    pub fn enable_tce(self, ext_feature: &ExtFeature) -> Result<Self, ArchError> {
+        if !ext_feature.support_tce() {
+            return Err(ArchError::FeatureIsNotSupported);
+        }
+        Ok(self.write::<fields::TCE>(true))
+    }
+}
 
 pub mod fields {
     bits::fields_ex! {
         super::EferBuffer [data] {
             INTWB   [18, rw, bool],
             MCOMMIT [17, rw, bool],
-            TCE     [15, rw, bool],
-            FFXSR   [14, rw, bool],
+            pub(super) TCE     [15, rw, bool],
+            pub(super) FFXSR   [14, rw, bool],
             LMSLE   [13, rw, bool],
-            SVME    [12, rw, bool],
+            pub(super) SVME    [12, rw, bool],
             /// 非执行页保护特性
-            NXE     [11, rw, bool],
+            pub(super) NXE     [11, rw, bool],
             /// 用于指示 64 位模式（long mode）是否被激活。
             ///
             /// 注意：该 bit 一般由处理器修改，系统软件虽然可修改，
@@ -119,7 +129,7 @@ pub mod fields {
             ///
             /// 激活 long 模式后，需要将 CS.L 置 1 才能进入到 64-bit 模式。
             LME     [08, rw, bool],
-            SCE     [00, rw, bool]
+            pub(super) SCE     [00, rw, bool]
         }
     }
 }
@@ -128,11 +138,28 @@ pub mod fields {
 mod test {
     use std::println;
 
-    use crate::msr::efer::{Efer, EferBuffer};
+    use crate::{
+        msr::{
+            efer::{fields, Efer, EferBuffer},
+            MsrBuffer,
+        },
+        Clean,
+    };
 
     #[test]
     fn size() {
         println!("{}", core::mem::size_of::<Efer>());
         println!("{}", core::mem::size_of::<EferBuffer>())
     }
+
+    #[test]
+    fn read_modify() {
+        // LMA | LME | SCE
+        let efer = Clean {
+            raw_buffer: EferBuffer::from_raw(0x0501),
+        };
+        assert!(efer.long_mode_activated());
+        let efer = efer.write::<fields::NXE>(true);
+        assert_eq!(efer.raw_buffer.raw(), 0x0d01);
+    }
 }
@@ -0,0 +1,214 @@
+use core::marker::PhantomData;
+
+use crate::{
+    cpuid::address::AddressWidths,
+    cr::cr4::{fields as cr4_fields, Cr4Buffer},
+    ArchError, Clean, Dirty,
+};
+
+use super::ModelSpecificRegister;
+
+/// # FS 段基址
+///
+/// 地址为 C000_0100h，64 bit 模式下 FS 段的基址，通常用于用户态的线程本地存储（TLS）。
+pub struct FsBase;
+
+impl ModelSpecificRegister for FsBase {
+    const REG_ADDR: u32 = 0xC000_0100;
+    type Buffer = FsBaseBuffer;
+}
+
+pub struct FsBaseBuffer {
+    data: u64,
+}
+
+/// # GS 段基址
+///
+/// 地址为 C000_0101h，64 bit 模式下 GS 段的基址，内核通常用于保存 per-CPU 数据的地址。
+pub struct GsBase;
+
+impl ModelSpecificRegister for GsBase {
+    const REG_ADDR: u32 = 0xC000_0101;
+    type Buffer = GsBaseBuffer;
+}
+
+pub struct GsBaseBuffer {
+    data: u64,
+}
+
+/// # 内核 GS 段基址
+///
+/// 地址为 C000_0102h，执行 [`swapgs`] 时与 GS 段基址互换。
+pub struct KernelGsBase;
+
+impl ModelSpecificRegister for KernelGsBase {
+    const REG_ADDR: u32 = 0xC000_0102;
+    type Buffer = KernelGsBaseBuffer;
+}
+
+pub struct KernelGsBaseBuffer {
+    data: u64,
+}
+
+impl_msr_buffer! {
+    FsBase => FsBaseBuffer;
+    GsBase => GsBaseBuffer;
+    KernelGsBase => KernelGsBaseBuffer;
+}
+
+/// 三个寄存器的格式相同，均为 64 bit 的线性地址，写入非规范地址会导致 #GP 异常。
+macro_rules! impl_segment_base {
+    ($($Buffer:ident: $Field:path),+ $(,)?) => {
+        $(
+            impl Clean<$Buffer> {
+                pub fn base(&self) -> u64 {
+                    self.read::<$Field>()
+                }
+            }
+            impl Dirty<$Buffer> {
+                /// 若 `base` 不是规范地址，则返回错误 `ArchError::AddressNotCanonical`。
+                pub fn set_base(self, base: u64, widths: &AddressWidths) -> Result<Self, ArchError> {
+                    if !widths.is_canonical(base) {
+                        return Err(ArchError::AddressNotCanonical);
+                    }
+                    Ok(self.write::<$Field>(base))
+                }
+            }
+        )+
+    };
+}
+
+impl_segment_base! {
+    FsBaseBuffer: fs_base_fields::BASE,
+    GsBaseBuffer: gs_base_fields::BASE,
+    KernelGsBaseBuffer: kernel_gs_base_fields::BASE,
+}
+
+pub mod fs_base_fields {
+    bits::fields_ex! {
+        super::FsBaseBuffer [data] {
+            pub(super) BASE [00..=63, rw, u64],
+        }
+    }
+}
+
+pub mod gs_base_fields {
+    bits::fields_ex! {
+        super::GsBaseBuffer [data] {
+            pub(super) BASE [00..=63, rw, u64],
+        }
+    }
+}
+
+pub mod kernel_gs_base_fields {
+    bits::fields_ex! {
+        super::KernelGsBaseBuffer [data] {
+            pub(super) BASE [00..=63, rw, u64],
+        }
+    }
+}
+
+/// 互换 GS 段基址与 [`KernelGsBase`] 中的值。
+///
+/// 只能在 64 bit 模式下、特权级别为 0 时执行，否则会导致 #UD 或 #GP 异常；
+/// 调用者需要保证进入和离开内核时成对执行，否则会使用用户态提供的 GS 基址。
+#[inline]
+#[cfg(target_arch = "x86_64")]
+pub unsafe fn swapgs() {
+    asm!("swapgs", options(nostack, preserves_flags));
+}
+
+/// # FSGSBASE 指令
+///
+/// `CR4.FSGSBASE = 1` 时，任何特权级别均可以通过 RDFSBASE、RDGSBASE、WRFSBASE、WRGSBASE 指令
+/// 读写段基址，比 RDMSR/WRMSR 快得多；否则执行这些指令会导致 #UD 异常。
+///
+/// [`FsGsBase::new`] 只检查创建时的 `CR4.FSGSBASE`，之后 CR4 仍可能被修改，
+/// 所以所有读写函数都是 unsafe 的，调用者需要保证调用时 `CR4.FSGSBASE` 仍为 1。
+pub struct FsGsBase {
+    phantom: PhantomData<usize>,
+}
+
+#[cfg(target_arch = "x86_64")]
+impl FsGsBase {
+    /// 若 `CR4.FSGSBASE` 未使能，则返回 None，参见 `Dirty<Cr4Buffer>::enable_fsgsbase`。
+    pub fn new(cr4_buffer: &Clean<Cr4Buffer>) -> Option<Self> {
+        if !cr4_buffer.read::<cr4_fields::FSGSBASE>() {
+            return None;
+        }
+        Some(Self {
+            phantom: PhantomData,
+        })
+    }
+
+    /// 调用者需要保证 `CR4.FSGSBASE` 仍为 1，否则会导致 #UD 异常。
+    #[inline]
+    pub unsafe fn fs_base(&self) -> u64 {
+        let base: u64;
+        asm!("rdfsbase {}", out(reg) base, options(nomem, nostack, preserves_flags));
+        base
+    }
+    /// 调用者需要保证 `CR4.FSGSBASE` 仍为 1，否则会导致 #UD 异常。
+    #[inline]
+    pub unsafe fn gs_base(&self) -> u64 {
+        let base: u64;
+        asm!("rdgsbase {}", out(reg) base, options(nomem, nostack, preserves_flags));
+        base
+    }
+    /// 若 `base` 不是规范地址，则返回错误 `ArchError::AddressNotCanonical`。
+    ///
+    /// ⚠️ 修改 FS 基址会影响所有通过 FS 访问的数据（例如编译器生成的 TLS 访问）。
+    #[inline]
+    pub unsafe fn set_fs_base(&self, base: u64, widths: &AddressWidths) -> Result<(), ArchError> {
+        if !widths.is_canonical(base) {
+            return Err(ArchError::AddressNotCanonical);
+        }
+        asm!("wrfsbase {}", in(reg) base, options(nostack, preserves_flags));
+        Ok(())
+    }
+    /// 若 `base` 不是规范地址，则返回错误 `ArchError::AddressNotCanonical`。
+    ///
+    /// ⚠️ 内核中修改的是当前的 GS 基址，执行 [`swapgs`] 后会与 [`KernelGsBase`] 互换。
+    #[inline]
+    pub unsafe fn set_gs_base(&self, base: u64, widths: &AddressWidths) -> Result<(), ArchError> {
+        if !widths.is_canonical(base) {
+            return Err(ArchError::AddressNotCanonical);
+        }
+        asm!("wrgsbase {}", in(reg) base, options(nostack, preserves_flags));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        cpuid::address::AddressWidths,
+        msr::{ModelSpecificRegister, MsrBuffer},
+        ArchError, Clean,
+    };
+
+    use super::{FsBase, GsBase, KernelGsBase, KernelGsBaseBuffer};
+
+    #[test]
+    fn set_base() {
+        let widths = AddressWidths {
+            physical: 39,
+            linear: 48,
+            guest_physical: 0,
+        };
+        assert_eq!(
+            (FsBase::REG_ADDR, GsBase::REG_ADDR, KernelGsBase::REG_ADDR),
+            (0xC000_0100, 0xC000_0101, 0xC000_0102)
+        );
+        let buffer = Clean {
+            raw_buffer: KernelGsBaseBuffer::from_raw(0),
+        }
+        .asume_dirty();
+        let buffer = buffer.set_base(0xffff_8880_0000_0000, &widths).unwrap();
+        assert_eq!(buffer.raw_buffer.raw(), 0xffff_8880_0000_0000);
+        assert!(matches!(
+            buffer.set_base(0x0000_8880_0000_0000, &widths),
+            Err(ArchError::AddressNotCanonical)
+        ));
+    }
+}
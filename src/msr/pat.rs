@@ -0,0 +1,301 @@
+use crate::{cpuid::feature::StdFeature, mem::mttr::MemType, ArchError, Clean, Dirty};
+
+use super::ModelSpecificRegister;
+
+/// # 页属性表
+/// Page Attribute Table
+///
+/// IA32_PAT 的地址为 277h，由 8 个 8 bit 的表项组成，每个表项的低 3 bit 为一个 [`MemType`]。
+/// 页表项中的 PAT、PCD、PWT 三个 bit 组成表项的索引 `PAT * 4 + PCD * 2 + PWT`，
+/// 用于选择该页的内存类型。
+pub struct Pat;
+
+impl ModelSpecificRegister for Pat {
+    const REG_ADDR: u32 = 0x0277;
+    type Buffer = PatBuffer;
+}
+
+impl Pat {
+    /// 需要 `CPUID.01H:EDX.PAT[16] = 1`，否则不存在该寄存器。
+    pub fn is_supported(std_feature: &StdFeature) -> bool {
+        std_feature.support_pat()
+    }
+}
+
+pub struct PatBuffer {
+    data: u64,
+}
+
+impl_msr_buffer!(Pat => PatBuffer);
+
+impl Clean<PatBuffer> {
+    pub fn layout(&self) -> PatLayout {
+        PatLayout::from_raw(self.raw_buffer.data)
+    }
+}
+
+impl Dirty<PatBuffer> {
+    /// ⚠️ 修改 PAT 后需要刷新 TLB 和缓存，并且所有处理器上的 PAT 应当保持一致。
+    pub fn set_layout(mut self, layout: &PatLayout) -> Self {
+        self.raw_buffer.data = layout.raw();
+        self
+    }
+}
+
+pub mod fields {
+    use crate::mem::mttr::MemType;
+
+    bits::fields_ex! {
+        super::PatBuffer [data] {
+            pub PA0 [00..=02, rw, MemType] {
+                input_converter: |x: MemType| x.data as u64;
+                output_converter: |data| MemType { data: data as u8 }
+            },
+            pub PA1 [08..=10, rw, MemType] {
+                input_converter: |x: MemType| x.data as u64;
+                output_converter: |data| MemType { data: data as u8 }
+            },
+            pub PA2 [16..=18, rw, MemType] {
+                input_converter: |x: MemType| x.data as u64;
+                output_converter: |data| MemType { data: data as u8 }
+            },
+            pub PA3 [24..=26, rw, MemType] {
+                input_converter: |x: MemType| x.data as u64;
+                output_converter: |data| MemType { data: data as u8 }
+            },
+            pub PA4 [32..=34, rw, MemType] {
+                input_converter: |x: MemType| x.data as u64;
+                output_converter: |data| MemType { data: data as u8 }
+            },
+            pub PA5 [40..=42, rw, MemType] {
+                input_converter: |x: MemType| x.data as u64;
+                output_converter: |data| MemType { data: data as u8 }
+            },
+            pub PA6 [48..=50, rw, MemType] {
+                input_converter: |x: MemType| x.data as u64;
+                output_converter: |data| MemType { data: data as u8 }
+            },
+            pub PA7 [56..=58, rw, MemType] {
+                input_converter: |x: MemType| x.data as u64;
+                output_converter: |data| MemType { data: data as u8 }
+            },
+        }
+    }
+}
+
+/// # PAT 布局
+///
+/// 8 个表项对应的内存类型，可以从默认布局开始逐项修改：
+///
+/// ```not test
+/// // 与 Linux 相同，将 PA1 改为 WC
+/// let layout = PatLayout::DEFAULT.with(1, MemType::WC)?;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PatLayout {
+    entries: [MemType; 8],
+}
+
+impl PatLayout {
+    /// 处理器上电或复位后的布局：WB、WT、UC-、UC，重复两次。
+    pub const DEFAULT: PatLayout = PatLayout {
+        entries: [
+            MemType::WB,
+            MemType::WT,
+            MemType::UC_MINUS,
+            MemType::UC,
+            MemType::WB,
+            MemType::WT,
+            MemType::UC_MINUS,
+            MemType::UC,
+        ],
+    };
+
+    pub const fn new(entries: [MemType; 8]) -> Self {
+        Self { entries }
+    }
+
+    /// 将第 `index` 个表项设置为 `mem_type`，`index` 大于 7 时返回错误 `ArchError::IndexOutOfRange`。
+    pub fn with(mut self, index: usize, mem_type: MemType) -> Result<Self, ArchError> {
+        let entry = self
+            .entries
+            .get_mut(index)
+            .ok_or(ArchError::IndexOutOfRange)?;
+        *entry = mem_type;
+        Ok(self)
+    }
+
+    pub fn entries(&self) -> &[MemType; 8] {
+        &self.entries
+    }
+
+    /// 不在 [`MemType`] 中的保留值（2、3）会被原样保留。
+    pub fn from_raw(raw: u64) -> Self {
+        let mut entries = [MemType::UC; 8];
+        for (i, entry) in entries.iter_mut().enumerate() {
+            entry.data = (raw >> (i * 8)) as u8 & 0b111;
+        }
+        Self { entries }
+    }
+
+    pub fn raw(&self) -> u64 {
+        self.entries
+            .iter()
+            .enumerate()
+            .fold(0, |raw, (i, entry)| raw | (entry.data as u64) << (i * 8))
+    }
+
+    /// 第一个类型为 `mem_type` 的表项对应的页表项缓存控制位，布局中不存在该类型时返回 None。
+    pub fn cache_bits(&self, mem_type: MemType) -> Option<PageCacheBits> {
+        self.entries
+            .iter()
+            .position(|&entry| entry == mem_type)
+            .map(|index| PageCacheBits::from_index(index as u8))
+    }
+
+    /// 页表项缓存控制位所选择的内存类型
+    pub fn mem_type(&self, bits: PageCacheBits) -> MemType {
+        self.entries[bits.index() as usize]
+    }
+}
+
+impl Default for PatLayout {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// # 页表项缓存控制位
+///
+/// PWT（bit 3）和 PCD（bit 4）在各级页表项中的位置相同，PAT 位的位置则取决于页表项的类型：
+///
+/// + 4KB 页的 PTE 中位于 bit 7；
+/// + 2MB、1GB 大页的 PDE、PDPTE 中位于 bit 12（bit 7 为 PS 位）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageCacheBits {
+    pub pat: bool,
+    pub pcd: bool,
+    pub pwt: bool,
+}
+
+impl PageCacheBits {
+    pub const PWT: u64 = 1 << 3;
+    pub const PCD: u64 = 1 << 4;
+    pub const PAT_4K: u64 = 1 << 7;
+    pub const PAT_LARGE: u64 = 1 << 12;
+
+    /// `index` 的低 3 bit 依次为 PWT、PCD、PAT。
+    pub const fn from_index(index: u8) -> Self {
+        Self {
+            pat: index & 0b100 != 0,
+            pcd: index & 0b010 != 0,
+            pwt: index & 0b001 != 0,
+        }
+    }
+
+    /// PAT 表项的索引
+    pub const fn index(&self) -> u8 {
+        (self.pat as u8) << 2 | (self.pcd as u8) << 1 | self.pwt as u8
+    }
+
+    /// 4KB 页 PTE 中对应的 bit
+    pub const fn pte_4k(&self) -> u64 {
+        self.low_bits() | if self.pat { Self::PAT_4K } else { 0 }
+    }
+
+    /// 大页 PDE、PDPTE 中对应的 bit
+    pub const fn large_page(&self) -> u64 {
+        self.low_bits() | if self.pat { Self::PAT_LARGE } else { 0 }
+    }
+
+    pub const fn from_pte_4k(entry: u64) -> Self {
+        Self {
+            pat: entry & Self::PAT_4K != 0,
+            pcd: entry & Self::PCD != 0,
+            pwt: entry & Self::PWT != 0,
+        }
+    }
+
+    pub const fn from_large_page(entry: u64) -> Self {
+        Self {
+            pat: entry & Self::PAT_LARGE != 0,
+            pcd: entry & Self::PCD != 0,
+            pwt: entry & Self::PWT != 0,
+        }
+    }
+
+    const fn low_bits(&self) -> u64 {
+        (if self.pcd { Self::PCD } else { 0 }) | (if self.pwt { Self::PWT } else { 0 })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        mem::mttr::MemType,
+        msr::{ModelSpecificRegister, MsrBuffer},
+        ArchError, Clean,
+    };
+
+    use super::{fields, PageCacheBits, Pat, PatBuffer, PatLayout};
+
+    #[test]
+    fn default_layout() {
+        assert_eq!(Pat::REG_ADDR, 0x277);
+        assert_eq!(PatLayout::DEFAULT.raw(), 0x0007_0406_0007_0406);
+        assert_eq!(
+            PatLayout::from_raw(0x0007_0406_0007_0406),
+            PatLayout::DEFAULT
+        );
+
+        let buffer = Clean {
+            raw_buffer: PatBuffer::from_raw(0x0007_0406_0007_0406),
+        };
+        assert_eq!(buffer.read::<fields::PA2>(), MemType::UC_MINUS);
+        assert_eq!(buffer.layout(), PatLayout::DEFAULT);
+    }
+
+    #[test]
+    fn custom_layout() {
+        let layout = PatLayout::DEFAULT
+            .with(1, MemType::WC)
+            .and_then(|layout| layout.with(7, MemType::WP))
+            .unwrap();
+        assert_eq!(layout.raw(), 0x0507_0406_0007_0106);
+        assert!(matches!(
+            layout.with(8, MemType::WC),
+            Err(ArchError::IndexOutOfRange)
+        ));
+
+        let buffer = Clean {
+            raw_buffer: PatBuffer::from_raw(0),
+        }
+        .asume_dirty()
+        .set_layout(&layout);
+        assert_eq!(buffer.raw_buffer.raw(), layout.raw());
+
+        let wc = layout.cache_bits(MemType::WC).unwrap();
+        assert_eq!(
+            wc,
+            PageCacheBits {
+                pat: false,
+                pcd: false,
+                pwt: true
+            }
+        );
+        assert_eq!(wc.pte_4k(), PageCacheBits::PWT);
+
+        let wp = layout.cache_bits(MemType::WP).unwrap();
+        assert_eq!(wp.index(), 7);
+        assert_eq!(wp.pte_4k(), 0x98);
+        assert_eq!(wp.large_page(), 0x1018);
+        assert_eq!(PageCacheBits::from_large_page(0x1018), wp);
+        assert_eq!(
+            layout.mem_type(PageCacheBits::from_pte_4k(0x98)),
+            MemType::WP
+        );
+
+        // 默认布局中不存在 WC
+        assert_eq!(PatLayout::DEFAULT.cache_bits(MemType::WC), None);
+    }
+}
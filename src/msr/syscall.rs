@@ -0,0 +1,471 @@
+use bits::field::BufferReader;
+
+use crate::{
+    cpuid::{address::AddressWidths, feature::ExtFeature},
+    cr::flags::FlagsBuffer,
+    mem::{
+        descriptor::{fields as descriptor_fields, Descriptor},
+        segment::selector::{Privilege, Selector},
+    },
+    ArchError, Clean,
+};
+
+use super::{efer::Efer, ModelSpecificRegister, Msr};
+
+/// # SYSCALL 目标地址寄存器
+/// SYSCALL Target Address Register
+///
+/// 地址为 C000_0081h，保存 SYSCALL/SYSRET 所使用的选择器：
+///
+/// + SYSCALL：`CS = STAR[47:32] & FFFCh`，`SS = STAR[47:32] + 8`；
+/// + 返回 64 bit 模式的 SYSRET：`CS = STAR[63:48] + 16`，`SS = STAR[63:48] + 8`；
+/// + 返回兼容模式的 SYSRET：`CS = STAR[63:48]`，`SS = STAR[63:48] + 8`。
+///
+/// SYSRET 会将选择器的 RPL 强制设置为 3。
+pub struct Star;
+
+impl ModelSpecificRegister for Star {
+    const REG_ADDR: u32 = 0xC000_0081;
+    type Buffer = StarBuffer;
+}
+
+pub struct StarBuffer {
+    data: u64,
+}
+
+impl_msr_buffer!(Star => StarBuffer);
+
+impl Clean<StarBuffer> {
+    /// SYSCALL 时加载的 CS
+    pub fn syscall_selector(&self) -> Selector {
+        self.read::<star_fields::SYSCALL_CS_SS>()
+    }
+    /// SYSRET 时使用的基准选择器
+    pub fn sysret_selector(&self) -> Selector {
+        self.read::<star_fields::SYSRET_CS_SS>()
+    }
+}
+
+/// # 64 bit 模式下 SYSCALL 的入口地址
+/// Long Mode SYSCALL Target Address Register
+///
+/// 地址为 C000_0082h，必须是规范地址，否则写入时会导致 #GP 异常。
+pub struct Lstar;
+
+impl ModelSpecificRegister for Lstar {
+    const REG_ADDR: u32 = 0xC000_0082;
+    type Buffer = LstarBuffer;
+}
+
+pub struct LstarBuffer {
+    data: u64,
+}
+
+impl_msr_buffer!(Lstar => LstarBuffer);
+
+impl Clean<LstarBuffer> {
+    pub fn target(&self) -> u64 {
+        self.read::<lstar_fields::TARGET>()
+    }
+}
+
+/// # 兼容模式下 SYSCALL 的入口地址
+/// Compatibility Mode SYSCALL Target Address Register
+///
+/// 地址为 C000_0083h；Intel 处理器不支持在兼容模式下执行 SYSCALL，但该寄存器仍然存在。
+pub struct Cstar;
+
+impl ModelSpecificRegister for Cstar {
+    const REG_ADDR: u32 = 0xC000_0083;
+    type Buffer = CstarBuffer;
+}
+
+pub struct CstarBuffer {
+    data: u64,
+}
+
+impl_msr_buffer!(Cstar => CstarBuffer);
+
+impl Clean<CstarBuffer> {
+    pub fn target(&self) -> u64 {
+        self.read::<cstar_fields::TARGET>()
+    }
+}
+
+/// # SYSCALL 标志位掩码
+/// SYSCALL Flag Mask Register
+///
+/// 地址为 C000_0084h，执行 SYSCALL 时，rFlags 中对应 bit 为 1 的标志位会被清 0。
+pub struct Sfmask;
+
+impl ModelSpecificRegister for Sfmask {
+    const REG_ADDR: u32 = 0xC000_0084;
+    type Buffer = SfmaskBuffer;
+}
+
+pub struct SfmaskBuffer {
+    data: u64,
+}
+
+impl_msr_buffer!(Sfmask => SfmaskBuffer);
+
+pub mod star_fields {
+    use crate::mem::segment::selector::Selector;
+
+    bits::fields_ex! {
+        super::StarBuffer [data] {
+            /// legacy 模式下 SYSCALL 的入口地址
+            pub SYSCALL_EIP     [00..=31, rw, u32],
+            /// SYSCALL 时加载的 CS，SS 为其下一个描述符
+            pub(super) SYSCALL_CS_SS   [32..=47, rw, Selector] {
+                input_converter: |selector: Selector| selector.raw() as u64;
+                output_converter: |data| Selector::from_raw(data as u16)
+            },
+            /// SYSRET 时使用的基准选择器，指向 32 bit 用户代码段
+            pub(super) SYSRET_CS_SS    [48..=63, rw, Selector] {
+                input_converter: |selector: Selector| selector.raw() as u64;
+                output_converter: |data| Selector::from_raw(data as u16)
+            },
+        }
+    }
+}
+
+pub mod lstar_fields {
+    bits::fields_ex! {
+        super::LstarBuffer [data] {
+            pub(super) TARGET  [00..=63, rw, u64],
+        }
+    }
+}
+
+pub mod cstar_fields {
+    bits::fields_ex! {
+        super::CstarBuffer [data] {
+            pub(super) TARGET  [00..=63, rw, u64],
+        }
+    }
+}
+
+pub mod sfmask_fields {
+    use crate::cr::flags::FlagsBuffer;
+
+    bits::fields_ex! {
+        super::SfmaskBuffer [data] {
+            /// 高 32 bit 保留
+            pub MASK    [00..=31, rw, FlagsBuffer] {
+                input_converter: |flags: FlagsBuffer| flags.data as u64;
+                output_converter: |data| FlagsBuffer { data: data as usize }
+            },
+        }
+    }
+}
+
+/// # SYSCALL/SYSRET 配置
+///
+/// STAR、LSTAR、CSTAR 中的选择器和入口地址只能通过 [`SyscallConfig::apply`] 写入。
+///
+/// SYSRET 通过对 `STAR[63:48]` 进行加法来得到用户态的选择器，因此 GDT 的布局必须满足：
+///
+/// ```text
+/// kernel_cs      64 bit 代码段，DPL = 0
+/// kernel_cs + 8  数据段，DPL = 0
+/// ...
+/// user_base      32 bit 代码段，DPL = 3（仅在配置了兼容模式入口时检查）
+/// user_base + 8  数据段，DPL = 3
+/// user_base + 16 64 bit 代码段，DPL = 3
+/// ```
+///
+/// 必须通过 [`entry`](Self::entry) 设置 64 bit 模式下的入口地址，
+/// [`validate`](Self::validate) 检查上述布局和入口地址后，才能通过 [`SyscallConfig::apply`] 写入寄存器并使能 `EFER.SCE`。
+pub struct SyscallBuilder {
+    kernel_cs: Selector,
+    user_base: Selector,
+    entry: Option<u64>,
+    compat_entry: Option<u64>,
+    mask: FlagsBuffer,
+}
+
+impl SyscallBuilder {
+    /// `kernel_cs` 的 RPL 必须为 0，`user_base` 的 RPL 必须为 3。
+    pub fn new(kernel_cs: Selector, user_base: Selector) -> Self {
+        Self {
+            kernel_cs,
+            user_base,
+            entry: None,
+            compat_entry: None,
+            mask: FlagsBuffer::empty(),
+        }
+    }
+    /// 64 bit 模式下的入口地址，即 LSTAR；不设置时 [`validate`](Self::validate) 返回错误。
+    pub fn entry(mut self, entry: u64) -> Self {
+        self.entry = Some(entry);
+        self
+    }
+    /// 兼容模式下的入口地址，即 CSTAR；不设置时 CSTAR 写入 0。
+    pub fn compat_entry(mut self, compat_entry: u64) -> Self {
+        self.compat_entry = Some(compat_entry);
+        self
+    }
+    /// 进入内核时需要清 0 的标志位，通常至少包含 IF、TF、DF 和 AC。
+    pub fn mask(mut self, mask: FlagsBuffer) -> Self {
+        self.mask = mask;
+        self
+    }
+
+    /// 检查选择器与 `gdt` 中的描述符是否满足 SYSCALL/SYSRET 的要求，以及入口地址是否是规范地址。
+    ///
+    /// 没有设置 64 bit 模式下的入口地址时返回错误 `ArchError::MissingConfiguration`。
+    pub fn validate(
+        &self,
+        gdt: &[Descriptor],
+        widths: &AddressWidths,
+    ) -> Result<SyscallConfig, ArchError> {
+        let entry = self.entry.ok_or(ArchError::MissingConfiguration)?;
+        if self.kernel_cs.rpl() != Privilege::RPL0 || self.user_base.rpl() != Privilege::RPL3 {
+            return Err(ArchError::InvalidSegmentLayout);
+        }
+        let kernel = self.kernel_cs.index();
+        let user = self.user_base.index();
+        check_segment(
+            gdt,
+            self.kernel_cs,
+            kernel,
+            Segment::Code64,
+            Privilege::DPL0,
+        )?;
+        check_segment(
+            gdt,
+            self.kernel_cs,
+            kernel + 1,
+            Segment::Data,
+            Privilege::DPL0,
+        )?;
+        if self.compat_entry.is_some() {
+            check_segment(gdt, self.user_base, user, Segment::Code32, Privilege::DPL3)?;
+        }
+        check_segment(
+            gdt,
+            self.user_base,
+            user + 1,
+            Segment::Data,
+            Privilege::DPL3,
+        )?;
+        check_segment(
+            gdt,
+            self.user_base,
+            user + 2,
+            Segment::Code64,
+            Privilege::DPL3,
+        )?;
+
+        let compat_entry = self.compat_entry.unwrap_or(0);
+        if !widths.is_canonical(entry) || !widths.is_canonical(compat_entry) {
+            return Err(ArchError::AddressNotCanonical);
+        }
+        Ok(SyscallConfig {
+            kernel_cs: self.kernel_cs,
+            user_base: self.user_base,
+            entry,
+            compat_entry,
+            mask: self.mask,
+        })
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Segment {
+    Code32,
+    Code64,
+    Data,
+}
+
+fn check_segment(
+    gdt: &[Descriptor],
+    selector: Selector,
+    index: u16,
+    segment: Segment,
+    dpl: Privilege,
+) -> Result<(), ArchError> {
+    if selector.ti() {
+        return Err(ArchError::InvalidSegmentLayout);
+    }
+    let descriptor = gdt
+        .get(index as usize)
+        .ok_or(ArchError::InvalidSegmentLayout)?;
+    let ty = descriptor.read::<descriptor_fields::Type>();
+    let is_code = ty & 0b1000 != 0;
+    let valid = descriptor.read::<descriptor_fields::P>()
+        && descriptor.read::<descriptor_fields::S>()
+        && descriptor.read::<descriptor_fields::DPL>() == dpl
+        && match segment {
+            Segment::Code64 => is_code && descriptor.read::<descriptor_fields::L>(),
+            Segment::Code32 => {
+                is_code
+                    && !descriptor.read::<descriptor_fields::L>()
+                    && descriptor.read::<descriptor_fields::DB>()
+            }
+            // 数据段必须可写
+            Segment::Data => !is_code && ty & 0b0010 != 0,
+        };
+    if valid {
+        Ok(())
+    } else {
+        Err(ArchError::InvalidSegmentLayout)
+    }
+}
+
+/// 经过检查的 SYSCALL/SYSRET 配置
+pub struct SyscallConfig {
+    kernel_cs: Selector,
+    user_base: Selector,
+    entry: u64,
+    compat_entry: u64,
+    mask: FlagsBuffer,
+}
+
+impl SyscallConfig {
+    /// 写入 STAR（保留 legacy 入口地址）、LSTAR、CSTAR、SFMASK，最后使能 `EFER.SCE`。
+    ///
+    /// 处理器不支持 SYSCALL 时返回错误 `ArchError::FeatureIsNotSupported`，此时不会写入任何寄存器。
+    ///
+    /// ⚠️ 调用者需要保证校验时传入的 GDT 就是当前加载的 GDT，且入口地址处是可执行的内核代码。
+    pub unsafe fn apply(&self, msr: &Msr, ext_feature: &ExtFeature) -> Result<(), ArchError> {
+        if !ext_feature.support_syscall() {
+            return Err(ArchError::FeatureIsNotSupported);
+        }
+        msr.buffer::<Star>()
+            .write::<star_fields::SYSCALL_CS_SS>(self.kernel_cs)
+            .write::<star_fields::SYSRET_CS_SS>(self.user_base)
+            .flush();
+        msr.buffer::<Lstar>()
+            .write::<lstar_fields::TARGET>(self.entry)
+            .flush();
+        msr.buffer::<Cstar>()
+            .write::<cstar_fields::TARGET>(self.compat_entry)
+            .flush();
+        msr.buffer::<Sfmask>()
+            .write::<sfmask_fields::MASK>(self.mask)
+            .flush();
+        msr.buffer::<Efer>()
+            .asume_dirty()
+            .enable_sce(ext_feature)?
+            .flush();
+        Ok(())
+    }
+    /// 写入 STAR 的高 32 bit
+    pub fn star_selectors(&self) -> u32 {
+        ((self.user_base.raw() as u32) << 16) | self.kernel_cs.raw() as u32
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use register::RegisterBufferWriter;
+
+    use crate::{
+        cpuid::address::AddressWidths,
+        cr::flags::{fields as flags_fields, FlagsBuffer},
+        mem::{
+            descriptor::Descriptor,
+            segment::selector::{Privilege, Selector},
+        },
+        msr::MsrBuffer,
+        ArchError, Clean,
+    };
+
+    use super::{sfmask_fields, star_fields, SfmaskBuffer, StarBuffer, SyscallBuilder};
+
+    const WIDTHS: AddressWidths = AddressWidths {
+        physical: 39,
+        linear: 48,
+        guest_physical: 0,
+    };
+
+    /// 与 Linux x86_64 相同的 GDT 布局
+    fn gdt() -> [Descriptor; 7] {
+        [
+            Descriptor::from_raw(0),
+            Descriptor::from_raw(0x00cf_9b00_0000_ffff),
+            Descriptor::from_raw(0x00af_9b00_0000_ffff),
+            Descriptor::from_raw(0x00cf_9300_0000_ffff),
+            Descriptor::from_raw(0x00cf_fb00_0000_ffff),
+            Descriptor::from_raw(0x00cf_f300_0000_ffff),
+            Descriptor::from_raw(0x00af_fb00_0000_ffff),
+        ]
+    }
+
+    #[test]
+    fn linux_layout() {
+        let kernel_cs = Selector::new(2, false, Privilege::RPL0);
+        let user_base = Selector::new(4, false, Privilege::RPL3);
+        let mut mask = FlagsBuffer::empty();
+        mask.write::<flags_fields::IF>(true)
+            .write::<flags_fields::DF>(true);
+        let config = SyscallBuilder::new(kernel_cs, user_base)
+            .entry(0xffff_ffff_8100_0000)
+            .compat_entry(0xffff_ffff_8100_1000)
+            .mask(mask)
+            .validate(&gdt(), &WIDTHS)
+            .unwrap();
+        assert_eq!(config.star_selectors(), 0x0023_0010);
+
+        let star = Clean {
+            raw_buffer: StarBuffer::from_raw(0xdead_beef),
+        }
+        .write::<star_fields::SYSCALL_CS_SS>(kernel_cs)
+        .write::<star_fields::SYSRET_CS_SS>(user_base);
+        assert_eq!(star.raw_buffer.raw(), 0x0023_0010_dead_beef);
+        assert_eq!(star.sysret_selector().raw(), 0x23);
+
+        let sfmask = Clean {
+            raw_buffer: SfmaskBuffer::from_raw(0),
+        }
+        .write::<sfmask_fields::MASK>(mask);
+        assert_eq!(sfmask.raw_buffer.raw(), 0x600);
+    }
+
+    #[test]
+    fn invalid_layout() {
+        let kernel_cs = Selector::new(2, false, Privilege::RPL0);
+        // 指向用户数据段，SYSRET 得到的 CS 会越界
+        let builder = SyscallBuilder::new(kernel_cs, Selector::new(5, false, Privilege::RPL3))
+            .entry(0xffff_ffff_8100_0000);
+        assert!(matches!(
+            builder.validate(&gdt(), &WIDTHS),
+            Err(ArchError::InvalidSegmentLayout)
+        ));
+        // GDT[1] 是 32 bit 代码段（L = 0），不能作为内核代码段
+        let builder = SyscallBuilder::new(
+            Selector::new(1, false, Privilege::RPL0),
+            Selector::new(4, false, Privilege::RPL3),
+        )
+        .entry(0xffff_ffff_8100_0000);
+        assert!(matches!(
+            builder.validate(&gdt(), &WIDTHS),
+            Err(ArchError::InvalidSegmentLayout)
+        ));
+        // 紧跟内核代码段的必须是可写、DPL = 0 的数据段
+        let builder = SyscallBuilder::new(kernel_cs, Selector::new(4, false, Privilege::RPL3))
+            .entry(0xffff_ffff_8100_0000);
+        for &kernel_ds in &[0x00cf_9100_0000_ffff, 0x00cf_f300_0000_ffff] {
+            let mut gdt = gdt();
+            gdt[3] = Descriptor::from_raw(kernel_ds);
+            assert!(matches!(
+                builder.validate(&gdt, &WIDTHS),
+                Err(ArchError::InvalidSegmentLayout)
+            ));
+        }
+        let builder = SyscallBuilder::new(kernel_cs, Selector::new(4, false, Privilege::RPL3))
+            .entry(0x0000_8000_0000_0000);
+        assert!(matches!(
+            builder.validate(&gdt(), &WIDTHS),
+            Err(ArchError::AddressNotCanonical)
+        ));
+        // 没有设置入口地址时不能通过检查，否则 LSTAR 会被写入 0
+        let builder = SyscallBuilder::new(kernel_cs, Selector::new(4, false, Privilege::RPL3));
+        assert!(matches!(
+            builder.validate(&gdt(), &WIDTHS),
+            Err(ArchError::MissingConfiguration)
+        ));
+    }
+}
@@ -1,38 +1,201 @@
-use super::{rdmsr, wrmsr};
+use crate::{
+    cpuid::{encryption::MemEncryption, vendor::Vendor},
+    ArchError, Clean, Dirty,
+};
 
+use super::{Msr, MsrBuffer};
+
+/// # 系统配置寄存器
+/// System Configuration Register
+///
+/// AMD 处理器的 SYSCFG 寄存器，其地址为 C001_0010h。
+///
+/// ⚠️ 其他制造商的处理器不存在该寄存器，读写会导致 #GP 异常，
+/// 所以该寄存器只能通过 [`Syscfg::buffer`] 读取，不能通过 `msr.buffer::<R>()` 读取。
 pub struct Syscfg;
-pub struct SyscfgBuffer {
-    data: u32,
-}
+
 impl Syscfg {
-    const REG_ADDR: u32 = 0xC001_0010;
-    pub unsafe fn buffer() -> SyscfgBuffer {
-        SyscfgBuffer {
-            data: rdmsr(Self::REG_ADDR) as u32,
+    pub const REG_ADDR: u32 = 0xC001_0010;
+
+    /// 仅 AMD 兼容的处理器（AMD、海光）存在该寄存器。
+    pub fn is_supported(vendor: Vendor) -> bool {
+        vendor.is_amd_compatible()
+    }
+    /// 读取 SYSCFG，不是 AMD 兼容的处理器时返回 None。
+    pub fn buffer(msr: &Msr, vendor: Vendor) -> Option<Clean<SyscfgBuffer>> {
+        if !Self::is_supported(vendor) {
+            return None;
         }
+        Some(unsafe { Self::buffer_uncheck(msr) })
     }
-}
-impl SyscfgBuffer {
-    pub fn flush(&mut self) {
-        unsafe {
-            wrmsr(Syscfg::REG_ADDR, self.data, 0);
+    /// 读取 SYSCFG，调用者需要保证处理器存在该寄存器，否则会触发 #GP 异常。
+    pub unsafe fn buffer_uncheck(msr: &Msr) -> Clean<SyscfgBuffer> {
+        Clean {
+            raw_buffer: SyscfgBuffer::from_raw(msr.read(Self::REG_ADDR)),
         }
     }
 }
 
-impl_reg_buffer_trait!(SyscfgBuffer);
+pub struct SyscfgBuffer {
+    data: u64,
+}
+
+impl_msr_buffer!(@addr Syscfg::REG_ADDR => SyscfgBuffer);
+
+impl Clean<SyscfgBuffer> {
+    pub fn vmpl_enabled(&self) -> bool {
+        self.read::<fields::VMPLE>()
+    }
+    pub fn snp_enabled(&self) -> bool {
+        self.read::<fields::SNPE>()
+    }
+    pub fn mem_encryption_enabled(&self) -> bool {
+        self.read::<fields::MEME>()
+    }
+    pub fn tom2_force_wb(&self) -> bool {
+        self.read::<fields::FWB>()
+    }
+    pub fn tom2_enabled(&self) -> bool {
+        self.read::<fields::TOM2>()
+    }
+    pub fn mtrr_var_dram_enabled(&self) -> bool {
+        self.read::<fields::MVDM>()
+    }
+    pub fn mtrr_fix_dram_modify_enabled(&self) -> bool {
+        self.read::<fields::MFDM>()
+    }
+    pub fn mtrr_fix_dram_enabled(&self) -> bool {
+        self.read::<fields::MFDE>()
+    }
+}
+
+impl Dirty<SyscfgBuffer> {
+    /// 使能 VMPL，需要 `CPUID.(EAX=8000_001Fh):EAX[5] = 1`，
+    /// 否则返回错误 `ArchError::FeatureIsNotSupported`。
+    pub fn enable_vmpl(self, encryption: &MemEncryption) -> Result<Self, ArchError> {
+        if !encryption.support_vmpl() {
+            return Err(ArchError::FeatureIsNotSupported);
+        }
+        Ok(self.write::<fields::VMPLE>(true))
+    }
+    /// 使能 SEV-SNP，需要 `CPUID.(EAX=8000_001Fh):EAX[4] = 1`，
+    /// 否则返回错误 `ArchError::FeatureIsNotSupported`。
+    pub fn enable_snp(self, encryption: &MemEncryption) -> Result<Self, ArchError> {
+        if !encryption.support_sev_snp() {
+            return Err(ArchError::FeatureIsNotSupported);
+        }
+        Ok(self.write::<fields::SNPE>(true))
+    }
+    /// 使能安全内存加密，需要 `CPUID.(EAX=8000_001Fh):EAX[0] = 1`，
+    /// 否则返回错误 `ArchError::FeatureIsNotSupported`。
+    ///
+    /// 使能后，页表项中 C-bit（参见 [`MemEncryption::encryption_mask`]）为 1 的页会被加密。
+    pub fn enable_mem_encryption(self, encryption: &MemEncryption) -> Result<Self, ArchError> {
+        if !encryption.support_sme() {
+            return Err(ArchError::FeatureIsNotSupported);
+        }
+        Ok(self.write::<fields::MEME>(true))
+    }
+    /// 设置 TOM2 相关的 bit，`force_wb` 为 true 时 4GB 到 TOM2 之间的内存类型为 WB。
+    ///
+    /// 不是 AMD 兼容的处理器时返回错误 `ArchError::FeatureIsNotSupported`。
+    pub fn set_tom2(self, vendor: Vendor, enable: bool, force_wb: bool) -> Result<Self, ArchError> {
+        if !Syscfg::is_supported(vendor) {
+            return Err(ArchError::FeatureIsNotSupported);
+        }
+        Ok(self
+            .write::<fields::TOM2>(enable)
+            .write::<fields::FWB>(force_wb))
+    }
+    /// 设置可变 MTRR 的 DRAM 使能位。
+    ///
+    /// 不是 AMD 兼容的处理器时返回错误 `ArchError::FeatureIsNotSupported`。
+    pub fn set_mtrr_var_dram(self, vendor: Vendor, enable: bool) -> Result<Self, ArchError> {
+        if !Syscfg::is_supported(vendor) {
+            return Err(ArchError::FeatureIsNotSupported);
+        }
+        Ok(self.write::<fields::MVDM>(enable))
+    }
+    /// 设置固定 MTRR 中 RdMem/WrMem 位的使能位（`enable`）和修改使能位（`modify`）。
+    ///
+    /// 不是 AMD 兼容的处理器时返回错误 `ArchError::FeatureIsNotSupported`。
+    ///
+    /// ⚠️ 修改完固定 MTRR 后应清除 `modify`，此后 RdMem/WrMem 位读出为 0。
+    pub fn set_mtrr_fix_dram(
+        self,
+        vendor: Vendor,
+        enable: bool,
+        modify: bool,
+    ) -> Result<Self, ArchError> {
+        if !Syscfg::is_supported(vendor) {
+            return Err(ArchError::FeatureIsNotSupported);
+        }
+        Ok(self
+            .write::<fields::MFDE>(enable)
+            .write::<fields::MFDM>(modify))
+    }
+}
 
 pub mod fields {
     bits::fields_ex! {
         super::SyscfgBuffer [data] {
-            VMPLE   [25, rw, bool],
-            SNPE    [24, rw, bool],
-            MEME    [23, rw, bool],
-            FWB     [22, rw, bool],
-            TOM2    [21, rw, bool],
-            MVDM    [20, rw, bool],
-            MFDM    [19, rw, bool],
-            MFDE    [18, rw, bool]
+            pub(super) VMPLE   [25, rw, bool],
+            pub(super) SNPE    [24, rw, bool],
+            /// 安全内存加密使能，参见 [`MemEncryption`](crate::cpuid::encryption::MemEncryption)
+            pub(super) MEME    [23, rw, bool],
+            pub(super) FWB     [22, rw, bool],
+            pub(super) TOM2    [21, rw, bool],
+            pub(super) MVDM    [20, rw, bool],
+            pub(super) MFDM    [19, rw, bool],
+            pub(super) MFDE    [18, rw, bool]
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        cpuid::{encryption::MemEncryption, vendor::Vendor, CpuidResult},
+        msr::{Msr, MsrBuffer},
+        ArchError, Clean,
+    };
+
+    use super::{Syscfg, SyscfgBuffer};
+
+    #[test]
+    fn gated_writes() {
+        // 其他制造商的处理器不会读取该寄存器
+        let msr = unsafe { Msr::inst_uncheck() };
+        assert!(Syscfg::buffer(&msr, Vendor::Intel).is_none());
+
+        // MFDE | MVDM | TOM2
+        let syscfg = Clean {
+            raw_buffer: SyscfgBuffer::from_raw(0x0034_0000),
+        };
+        assert!(syscfg.mtrr_fix_dram_enabled() && !syscfg.mtrr_fix_dram_modify_enabled());
+        assert!(syscfg.tom2_enabled() && !syscfg.mem_encryption_enabled());
+
+        // 支持 SME，不支持 SEV-SNP
+        let encryption = MemEncryption::from_result(&CpuidResult {
+            eax: 0x1,
+            ebx: 0x16f,
+            ..Default::default()
+        });
+        assert!(matches!(
+            syscfg.asume_dirty().enable_snp(&encryption),
+            Err(ArchError::FeatureIsNotSupported)
+        ));
+
+        let syscfg = Clean {
+            raw_buffer: SyscfgBuffer::from_raw(0x0034_0000),
         }
+        .asume_dirty()
+        .enable_mem_encryption(&encryption)
+        .unwrap();
+        assert_eq!(syscfg.raw_buffer.raw(), 0x00b4_0000);
+        assert!(matches!(
+            syscfg.set_mtrr_var_dram(Vendor::Intel, false),
+            Err(ArchError::FeatureIsNotSupported)
+        ));
     }
 }